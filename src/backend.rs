@@ -0,0 +1,150 @@
+use plotters::prelude::*;
+use plotters_backend::{BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingErrorKind};
+use std::{error::Error, io};
+
+/// A drawing backend for one of the supported image formats.
+///
+/// All calls are delegated to the wrapped backend, so that e.g. texts stay texts in SVG files.
+pub enum FileBackend<'a> {
+    Bitmap(BitMapBackend<'a>),
+    Svg(SVGBackend<'a>),
+}
+
+/// Converts the errors of the wrapped backends into I/O errors, the error type of the SVG backend.
+fn to_io_error<E: Error + Send + Sync + 'static>(error: DrawingErrorKind<E>) -> DrawingErrorKind<io::Error> {
+    match error {
+        DrawingErrorKind::DrawingError(error) => DrawingErrorKind::DrawingError(io::Error::other(error)),
+        DrawingErrorKind::FontError(error) => DrawingErrorKind::FontError(error),
+    }
+}
+
+impl DrawingBackend for FileBackend<'_> {
+    type ErrorType = io::Error;
+
+    fn get_size(&self) -> (u32, u32) {
+        match self {
+            FileBackend::Bitmap(backend) => backend.get_size(),
+            FileBackend::Svg(backend) => backend.get_size(),
+        }
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<io::Error>> {
+        match self {
+            FileBackend::Bitmap(backend) => backend.ensure_prepared().map_err(to_io_error),
+            FileBackend::Svg(backend) => backend.ensure_prepared(),
+        }
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<io::Error>> {
+        match self {
+            FileBackend::Bitmap(backend) => backend.present().map_err(to_io_error),
+            FileBackend::Svg(backend) => backend.present(),
+        }
+    }
+
+    fn draw_pixel(&mut self, point: BackendCoord, color: BackendColor) -> Result<(), DrawingErrorKind<io::Error>> {
+        match self {
+            FileBackend::Bitmap(backend) => backend.draw_pixel(point, color).map_err(to_io_error),
+            FileBackend::Svg(backend) => backend.draw_pixel(point, color),
+        }
+    }
+
+    fn draw_line<S: BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<io::Error>> {
+        match self {
+            FileBackend::Bitmap(backend) => backend.draw_line(from, to, style).map_err(to_io_error),
+            FileBackend::Svg(backend) => backend.draw_line(from, to, style),
+        }
+    }
+
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<io::Error>> {
+        match self {
+            FileBackend::Bitmap(backend) => backend
+                .draw_rect(upper_left, bottom_right, style, fill)
+                .map_err(to_io_error),
+            FileBackend::Svg(backend) => backend.draw_rect(upper_left, bottom_right, style, fill),
+        }
+    }
+
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        path: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<io::Error>> {
+        match self {
+            FileBackend::Bitmap(backend) => backend.draw_path(path, style).map_err(to_io_error),
+            FileBackend::Svg(backend) => backend.draw_path(path, style),
+        }
+    }
+
+    fn draw_circle<S: BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<io::Error>> {
+        match self {
+            FileBackend::Bitmap(backend) => backend
+                .draw_circle(center, radius, style, fill)
+                .map_err(to_io_error),
+            FileBackend::Svg(backend) => backend.draw_circle(center, radius, style, fill),
+        }
+    }
+
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vert: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<io::Error>> {
+        match self {
+            FileBackend::Bitmap(backend) => backend.fill_polygon(vert, style).map_err(to_io_error),
+            FileBackend::Svg(backend) => backend.fill_polygon(vert, style),
+        }
+    }
+
+    fn draw_text<TStyle: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &TStyle,
+        pos: BackendCoord,
+    ) -> Result<(), DrawingErrorKind<io::Error>> {
+        match self {
+            FileBackend::Bitmap(backend) => backend.draw_text(text, style, pos).map_err(to_io_error),
+            FileBackend::Svg(backend) => backend.draw_text(text, style, pos),
+        }
+    }
+
+    fn estimate_text_size<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle,
+    ) -> Result<(u32, u32), DrawingErrorKind<io::Error>> {
+        match self {
+            FileBackend::Bitmap(backend) => backend.estimate_text_size(text, style).map_err(to_io_error),
+            FileBackend::Svg(backend) => backend.estimate_text_size(text, style),
+        }
+    }
+
+    fn blit_bitmap(
+        &mut self,
+        pos: BackendCoord,
+        size: (u32, u32),
+        src: &[u8],
+    ) -> Result<(), DrawingErrorKind<io::Error>> {
+        match self {
+            FileBackend::Bitmap(backend) => backend.blit_bitmap(pos, size, src).map_err(to_io_error),
+            FileBackend::Svg(backend) => backend.blit_bitmap(pos, size, src),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::plot::PlotTask;
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// Hashes of the series computed in previous runs, stored as JSON in a state file.
+///
+/// Tasks are identified by their output file name. As the hashes depend on the Rust version,
+/// upgrading it just re-renders all charts once.
+#[derive(Debug)]
+pub struct SeriesCache {
+    path: PathBuf,
+    hashes: HashMap<String, String>,
+}
+impl SeriesCache {
+    /// Loads the cache from the given state file, starting with an empty cache if it does not exist yet.
+    pub fn load(path: &Path) -> Result<SeriesCache, Error> {
+        let hashes = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(error) if error.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(error) => return Err(error),
+        };
+        Ok(SeriesCache {
+            path: path.to_path_buf(),
+            hashes,
+        })
+    }
+
+    /// Checks whether the task's series is the same as in the previous run, and its image still exists.
+    pub fn is_unchanged(&self, task: &PlotTask, series: &[(String, f64)]) -> bool {
+        Path::new(&task.output_file_name).is_file()
+            && self.hashes.get(&task.output_file_name) == Some(&series_hash(task, series))
+    }
+
+    /// Stores the hash of the task's (newly rendered) series.
+    pub fn update(&mut self, task: &PlotTask, series: &[(String, f64)]) {
+        self.hashes
+            .insert(task.output_file_name.clone(), series_hash(task, series));
+    }
+
+    /// Writes the cache back to its state file.
+    pub fn save(&self) -> Result<(), Error> {
+        fs::write(&self.path, serde_json::to_string_pretty(&self.hashes)?)
+    }
+}
+
+/// Hashes the task configuration together with its series, so that changing either triggers re-rendering.
+fn series_hash(task: &PlotTask, series: &[(String, f64)]) -> String {
+    let mut hasher = DefaultHasher::new();
+    format!("{:?}", task).hash(&mut hasher);
+    for (label, value) in series {
+        label.hash(&mut hasher);
+        value.to_bits().hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
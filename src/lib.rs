@@ -1,7 +1,21 @@
-use plot::{PlotTask, TimeResolution};
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use chrono::{DateTime, Datelike, DurationRound, TimeDelta, Timelike, Utc};
+use chrono_tz::Tz;
+use cache::SeriesCache;
+use plot::PlotTask;
+use rayon::{
+    ThreadPoolBuilder,
+    iter::{IntoParallelRefIterator, ParallelIterator},
+};
 use slack::MessageInChannel;
-use std::{io::Error, result::Result};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    io::Error,
+    path::Path,
+    result::Result,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 /// Slack JSON data structures and parsing
 pub mod slack;
@@ -9,82 +23,2233 @@ pub mod slack;
 /// Plotting utilities
 pub mod plot;
 
+/// Statistical helpers for time series
+pub mod stats;
+
+/// Exporting computed series to other formats
+pub mod output;
+
+/// Drawing backends for the supported image formats
+pub mod backend;
+
+/// Destinations for the images and CSV files written when plotting
+pub mod sink;
+
+/// Remembering computed series across runs, to skip re-rendering unchanged charts
+pub mod cache;
+
+/// Splitting message texts into words
+pub mod text;
+
+/// The time series computed (and plotted) for a task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskSeries {
+    pub task: PlotTask,
+    pub series: Vec<(String, f64)>,
+}
+
+/// Runs all tasks like `process_tasks_with_policy`, but only renders the charts whose series changed since the
+/// previous run, as recorded in the given cache state file (which is updated afterwards).
+///
+/// Changed tasks are processed again with output, so their charts are the same as in an uncached run.
+pub fn process_tasks_cached(
+    tasks: &[PlotTask],
+    messages: &[MessageInChannel],
+    cache_file: &Path,
+    policy: ErrorPolicy,
+) -> Result<Vec<TaskSeries>, Error> {
+    let mut cache = SeriesCache::load(cache_file)?;
+    let computing_tasks: Vec<PlotTask> = tasks.iter().map(PlotTask::without_output).collect();
+    let (unchanged, changed): (Vec<TaskSeries>, Vec<TaskSeries>) =
+        process_tasks_with_policy(&computing_tasks, messages, policy)?
+            .into_iter()
+            .map(|result| TaskSeries {
+                task: PlotTask {
+                    skip_output: false,
+                    ..result.task
+                },
+                series: result.series,
+            })
+            .partition(|result| cache.is_unchanged(&result.task, &result.series));
+    println!(
+        "Skipping {} of {} tasks, as their series did not change.",
+        unchanged.len(),
+        tasks.len()
+    );
+    let changed_tasks: Vec<PlotTask> = changed.into_iter().map(|result| result.task).collect();
+    let rendered = process_tasks_with_policy(&changed_tasks, messages, policy)?;
+    for result in &rendered {
+        cache.update(&result.task, &result.series);
+    }
+    cache.save()?;
+    Ok(tasks
+        .iter()
+        .filter_map(|task| unchanged.iter().chain(&rendered).find(|result| &result.task == task).cloned())
+        .collect())
+}
+
+/// Runs all tasks on two exports (A and B) and plots the difference of their series (B minus A) per label.
+///
+/// Labels missing in one of the exports count as zero there.
+pub fn process_tasks_diff(
+    tasks: &[PlotTask],
+    messages_a: &[MessageInChannel],
+    messages_b: &[MessageInChannel],
+    policy: ErrorPolicy,
+) -> Result<Vec<TaskSeries>, Error> {
+    let computing_tasks: Vec<PlotTask> = tasks.iter().map(PlotTask::without_output).collect();
+    let results_a = process_tasks_with_policy(&computing_tasks, messages_a, policy)?;
+    let results_b = process_tasks_with_policy(&computing_tasks, messages_b, policy)?;
+    let mut diffs: Vec<TaskSeries> = Vec::new();
+    for task in tasks {
+        let computing_task = task.without_output();
+        let series_of = |results: &[TaskSeries]| {
+            results
+                .iter()
+                .find(|result| result.task == computing_task)
+                .map(|result| result.series.clone())
+        };
+        // Tasks that failed on either export (without aborting) are left out
+        let (Some(series_a), Some(series_b)) = (series_of(&results_a), series_of(&results_b)) else {
+            continue;
+        };
+        let diff = series_diff(&series_a, &series_b);
+        plot::line_plot(task, "difference (B - A)", &diff).map_err(|error| {
+            Error::other(format!("Task '{}' failed: {}", task.output_file_name, error))
+        })?;
+        diffs.push(TaskSeries {
+            task: task.clone(),
+            series: diff,
+        });
+    }
+    Ok(diffs)
+}
+
+/// Subtracts series A from series B per label, treating missing labels as zero; the result is sorted by label.
+fn series_diff(series_a: &[(String, f64)], series_b: &[(String, f64)]) -> Vec<(String, f64)> {
+    let mut diff: BTreeMap<String, f64> = BTreeMap::new();
+    for (label, value) in series_a {
+        *diff.entry(label.clone()).or_insert(0.0) -= value;
+    }
+    for (label, value) in series_b {
+        *diff.entry(label.clone()).or_insert(0.0) += value;
+    }
+    diff.into_iter().collect()
+}
+
+/// Runs all tasks like `process_tasks_with_policy` (aborting at the first failing task), but on a dedicated
+/// thread pool with the given number of threads instead of rayon's global pool.
+/// With a single thread, tasks are processed sequentially.
+pub fn process_tasks_with_threads(
+    tasks: &[PlotTask],
+    messages: &[MessageInChannel],
+    threads: usize,
+) -> Result<Vec<TaskSeries>, Error> {
+    run_with_threads(threads, || process_tasks_with_policy(tasks, messages, ErrorPolicy::Abort))?
+}
+
+/// Runs the operation on a new thread pool with the given number of threads.
+pub fn run_with_threads<T, F>(threads: usize, operation: F) -> Result<T, Error>
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(Error::other)?;
+    Ok(pool.install(operation))
+}
+
+/// What to do when a task fails, e.g. because its image cannot be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Stop processing and return the error.
+    Abort,
+    /// Log the error and continue with the other tasks.
+    #[default]
+    Continue,
+    /// Try the task once more, and continue with the other tasks if it fails again.
+    Retry,
+}
+impl FromStr for ErrorPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "abort" => Ok(ErrorPolicy::Abort),
+            "continue" => Ok(ErrorPolicy::Continue),
+            "retry" => Ok(ErrorPolicy::Retry),
+            _ => Err(format!(
+                "Unknown error policy '{}', expected 'abort', 'continue', or 'retry'.",
+                policy
+            )),
+        }
+    }
+}
+
+/// The outcome of a task: the plotted series if it succeeded, or the error message if it failed,
+/// along with the number of messages the task matched and how long it took (including any retry).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskOutcome {
+    pub task: PlotTask,
+    pub result: Result<Vec<(String, f64)>, String>,
+    pub match_count: usize,
+    pub duration: Duration,
+}
+impl TaskOutcome {
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Runs all tasks in parallel, plotting their results and returning the outcome of each task.
+/// Failing tasks do not stop the others, so the outcomes show which tasks succeeded.
 pub fn process_tasks(
     tasks: &[PlotTask],
     messages: &[MessageInChannel],
-) -> Result<(), Error> {
-    tasks.par_iter().for_each(|task| {
-        println!("Task: {:?}", task);
-            match task.metric {
-                plot::Metric::MentionCount {
-                    ref channel_pattern,
-                    ref message_pattern,
-                } => {
-                    let message_counts = filter_and_count_messages(
-                        &messages,
-                        channel_pattern,
-                        message_pattern,
-                        &task.resolution,
-                    );
-                    plot::counter_plot(&task, &message_pattern, &message_counts)
-                        .expect("Image generation failed.");
-                }
-                plot::Metric::StringMessageCountRatio {
-                    ref channel_pattern,
-                    ref message_pattern1,
-                    ref message_pattern2,
-                } => {
-                    let message_counts1 = filter_and_count_messages(
-                        &messages,
-                        channel_pattern,
-                        message_pattern1,
-                        &task.resolution,
-                    );
-                    let message_counts2 = filter_and_count_messages(
-                        &messages,
-                        channel_pattern,
-                        message_pattern2,
-                        &task.resolution,
-                    );
-
-                    plot::ratio_plot(
-                        &task,
-                        &message_pattern1,
-                        &message_counts1,
-                        &message_pattern2,
-                        &message_counts2,
-                    )
-                    .expect("Image generation failed.");
-                }
-            }
+) -> Result<Vec<TaskOutcome>, Error> {
+    process_tasks_with_outcomes(tasks, messages, ErrorPolicy::Continue)
+}
+
+/// Runs a single task, trying it once more if it fails and `retry` is set.
+/// Returns the result along with the number of messages the (last) run matched.
+fn run_task(
+    task: &PlotTask,
+    messages: &[MessageInChannel],
+    retry: bool,
+) -> (Result<Vec<(String, f64)>, String>, usize) {
+    println!("Task: {:?}", task);
+    // Errors are converted to strings, as they need to be sent between threads
+    let (mut result, mut match_count) = process_task_counting_matches(task, messages);
+    if retry && let Err(error) = &result {
+        eprintln!("Task '{}' failed ({}), retrying.", task.output_file_name, error);
+        (result, match_count) = process_task_counting_matches(task, messages);
+    }
+    (result.map_err(|error| error.to_string()), match_count)
+}
+
+/// Runs a task like `run_task`, timing it and counting the messages it matches.
+fn run_task_timed(task: &PlotTask, messages: &[MessageInChannel], retry: bool) -> TaskOutcome {
+    let start = Instant::now();
+    let (result, match_count) = run_task(task, messages, retry);
+    TaskOutcome {
+        task: task.clone(),
+        result,
+        match_count,
+        duration: start.elapsed(),
+    }
+}
+
+/// Runs all tasks in parallel, handling failing tasks according to the given policy,
+/// and returns the outcome of each task (including the failed ones, unless the run was aborted).
+pub fn process_tasks_with_outcomes(
+    tasks: &[PlotTask],
+    messages: &[MessageInChannel],
+    policy: ErrorPolicy,
+) -> Result<Vec<TaskOutcome>, Error> {
+    tasks
+        .par_iter()
+        .map(|task| {
+            let outcome = run_task_timed(task, messages, policy == ErrorPolicy::Retry);
+            match &outcome.result {
+                Err(error) if policy == ErrorPolicy::Abort => Err(Error::other(format!(
+                    "Task '{}' failed: {}",
+                    task.output_file_name, error
+                ))),
+                Err(error) => {
+                    eprintln!("Skipping failed task '{}': {}", task.output_file_name, error);
+                    Ok(outcome)
+                }
+                Ok(_) => Ok(outcome),
+            }
+        })
+        .collect()
+}
+
+/// Runs all tasks in parallel, handling failing tasks according to the given policy.
+/// Tasks that failed without aborting the run are left out of the results.
+pub fn process_tasks_with_policy(
+    tasks: &[PlotTask],
+    messages: &[MessageInChannel],
+    policy: ErrorPolicy,
+) -> Result<Vec<TaskSeries>, Error> {
+    Ok(successful_series(process_tasks_with_outcomes(tasks, messages, policy)?))
+}
+
+/// Returns the series of the successful tasks, leaving out the failed ones.
+pub fn successful_series(outcomes: Vec<TaskOutcome>) -> Vec<TaskSeries> {
+    outcomes
+        .into_iter()
+        .filter_map(|outcome| {
+            Some(TaskSeries {
+                task: outcome.task,
+                series: outcome.result.ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Counts the messages of a `MentionCount` task while consuming them (e.g. from `slack::ZipMessages`), so that
+/// the counts of large exports can be shown while they accumulate. After every `progress_interval` consumed
+/// messages, and once all are consumed, `on_progress` is called with the counts per time bucket so far.
+///
+/// Returns the same counts as processing the task on all messages at once, but without plotting them.
+pub fn count_messages_progressively<I, F>(
+    task: &PlotTask,
+    messages: I,
+    progress_interval: usize,
+    mut on_progress: F,
+) -> Result<Vec<(String, usize)>, Box<dyn std::error::Error>>
+where
+    I: IntoIterator<Item = MessageInChannel>,
+    F: FnMut(&BTreeMap<String, usize>),
+{
+    task.validate()?;
+    let plot::Metric::MentionCount {
+        ref channel_pattern,
+        ref message_pattern,
+    } = task.metric
+    else {
+        return Err(format!("Counting progressively is not supported for {} tasks.", task.metric.name()).into());
+    };
+    let channel_matcher = matcher(task, channel_pattern);
+    let message_matcher = matcher(task, message_pattern);
+    let user_matcher = user_matcher(task);
+    let excluded_subtypes = task.excluded_subtypes();
+    // Labels are sortable, so the buckets are in time order even though the messages are not
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for (index, message) in messages.into_iter().enumerate() {
+        if channel_matcher.is_match(&message.channel)
+            && is_included(task, &message)
+            && is_by_matching_user(&message, user_matcher.as_ref())
+            && message.message.contains(&message_matcher)
+            && message.message.is_countable(&excluded_subtypes)
+        {
+            *counts.entry(time_by_resolution(&message, task)).or_insert(0) += 1;
+        }
+        if (index + 1) % progress_interval.max(1) == 0 {
+            on_progress(&counts);
+        }
+    }
+    on_progress(&counts);
+    Ok(fill_empty_buckets(counts.into_iter().collect(), &task.resolution))
+}
+
+/// Validates and processes the task, returning its series along with the number of distinct messages its metric
+/// selected while computing the series (i.e. those in matching channels that pass all of the metric's filters).
+#[allow(clippy::type_complexity)]
+fn process_task_counting_matches(
+    task: &PlotTask,
+    messages: &[MessageInChannel],
+) -> (Result<Vec<(String, f64)>, Box<dyn std::error::Error>>, usize) {
+    if let Err(error) = task.validate() {
+        return (Err(error), 0);
+    }
+    if task.emit_timestamps.unwrap_or(false)
+        && let Err(error) = write_matching_timestamps(task, messages)
+    {
+        return (Err(error), 0);
+    }
+    SELECTED_MESSAGES.with(|selected| selected.replace(Some(HashSet::new())));
+    let series = compute_series(task, messages);
+    let match_count = SELECTED_MESSAGES.with(|selected| selected.take().map_or(0, |selected| selected.len()));
+    (series, match_count)
+}
+
+/// Computes (and plots) the series of the task's metric, without validating the task.
+fn compute_series(
+    task: &PlotTask,
+    messages: &[MessageInChannel],
+) -> Result<Vec<(String, f64)>, Box<dyn std::error::Error>> {
+    let series = match task.metric {
+        plot::Metric::MentionCount {
+            ref channel_pattern,
+            ref message_pattern,
+        } => {
+            let description = format!("messages mentioning '{}'", message_pattern);
+            if task.stack_by_channel.unwrap_or(false) {
+                let named_counts =
+                    filter_and_count_messages_by_channel(messages, task, channel_pattern, message_pattern);
+                plot::stacked_plot(task, &format!("{} per channel", description), &named_counts)?;
+                flatten_named_series(
+                    named_counts
+                        .into_iter()
+                        .map(|(channel, counts)| (channel, to_values(&counts)))
+                        .collect(),
+                )
+            } else {
+                let message_counts =
+                    filter_and_count_messages(messages, task, channel_pattern, message_pattern);
+                plot_channel_counts(task, messages, channel_pattern, &description, &message_counts)?
+            }
+        }
+        plot::Metric::StringMessageCountRatio {
+            ref channel_pattern,
+            ref message_pattern1,
+            ref message_pattern2,
+        } => {
+            let message_counts1 =
+                filter_and_count_messages(messages, task, channel_pattern, message_pattern1);
+            let message_counts2 =
+                filter_and_count_messages(messages, task, channel_pattern, message_pattern2);
+
+            plot::ratio_plot(
+                task,
+                message_pattern1,
+                &message_counts1,
+                message_pattern2,
+                &message_counts2,
+            )?
+        }
+        plot::Metric::EditRate {
+            ref channel_pattern,
+            ref message_pattern,
+        } => {
+            let (edited_counts, unedited_counts) = filter_and_split_messages(
+                messages,
+                task,
+                channel_pattern,
+                message_pattern,
+                |x| x.message.is_edited(),
+            );
+            plot::ratio_plot(task, "edited", &edited_counts, "unedited", &unedited_counts)?
+        }
+        plot::Metric::ExclamationRatio {
+            ref channel_pattern,
+            ref message_pattern,
+        } => {
+            let (excited_counts, neutral_counts) = filter_and_split_messages(
+                messages,
+                task,
+                channel_pattern,
+                message_pattern,
+                |x| x.message.text.contains('!'),
+            );
+            plot::ratio_plot(task, "excited", &excited_counts, "neutral", &neutral_counts)?
+        }
+        plot::Metric::AllCapsRatio {
+            ref channel_pattern,
+            ref message_pattern,
+        } => {
+            let (shouting_counts, other_counts) = filter_and_split_messages(
+                messages,
+                task,
+                channel_pattern,
+                message_pattern,
+                |x| x.message.is_all_caps(),
+            );
+            plot::ratio_plot(task, "all caps", &shouting_counts, "other", &other_counts)?
+        }
+        plot::Metric::MultiMentionCount {
+            ref channel_pattern,
+            ref message_patterns,
+        } => {
+            let named_counts: Vec<(String, Vec<(String, usize)>)> = message_patterns
+                .iter()
+                .map(|pattern| {
+                    let counts = filter_and_count_messages(messages, task, channel_pattern, pattern);
+                    (pattern.clone(), counts)
+                })
+                .collect();
+            plot::multi_counter_plot(task, "mentions per pattern", &named_counts)?;
+            flatten_named_series(
+                named_counts
+                    .into_iter()
+                    .map(|(pattern, counts)| (pattern, to_values(&counts)))
+                    .collect(),
+            )
+        }
+        plot::Metric::MentionCountByUser {
+            ref channel_pattern,
+            ref message_pattern,
+            top_n,
+        } => {
+            let named_counts = filter_and_count_messages_by_user(messages, task, channel_pattern, message_pattern, top_n);
+            plot::stacked_plot(task, "mentions per user", &named_counts)?;
+            flatten_named_series(
+                named_counts
+                    .into_iter()
+                    .map(|(user, counts)| (user, to_values(&counts)))
+                    .collect(),
+            )
+        }
+        plot::Metric::ResponseCoverage {
+            ref channel_pattern,
+            sla_hours,
+        } => {
+            let (covered_counts, uncovered_counts) =
+                split_questions_by_response(messages, task, channel_pattern, sla_hours);
+            plot::ratio_plot(task, "answered", &covered_counts, "not answered", &uncovered_counts)?
+        }
+        plot::Metric::FirstResponseTime {
+            ref channel_pattern,
+        } => {
+            let named_series = first_response_minutes(messages, task, channel_pattern);
+            plot::multi_line_plot(task, "minutes to first response", &named_series)?;
+            flatten_named_series(named_series)
+        }
+        plot::Metric::QuietHoursCount {
+            ref channel_pattern,
+            start_hour,
+            end_hour,
+            ref timezone,
+        } => {
+            let time_zone = match timezone {
+                Some(timezone) => plot::parse_time_zone(timezone)?,
+                None => task.time_zone()?,
+            };
+            let messages_to_plot = find_messages(messages, task, channel_pattern, |x| {
+                x.message
+                    .time()
+                    .is_some_and(|time| is_quiet_hour(time.with_timezone(&time_zone).hour(), start_hour, end_hour))
+            });
+            println!("Found {} messages during quiet hours.", messages_to_plot.len());
+            let message_counts = group_messages_by_time(&messages_to_plot, task);
+            plot_channel_counts(
+                task,
+                messages,
+                channel_pattern,
+                "messages during quiet hours",
+                &message_counts,
+            )?
+        }
+        plot::Metric::LinkCount {
+            ref channel_pattern,
+            ref url_pattern,
+        } => {
+            let message_counts =
+                filter_and_count_linking_messages(messages, task, channel_pattern, url_pattern);
+            plot_channel_counts(
+                task,
+                messages,
+                channel_pattern,
+                &format!("messages linking to '{}'", url_pattern),
+                &message_counts,
+            )?
+        }
+        plot::Metric::BurstCount {
+            ref channel_pattern,
+            ref message_pattern,
+            burst_factor,
+        } => {
+            let message_counts =
+                filter_and_count_messages(messages, task, channel_pattern, message_pattern);
+            plot::highlighted_plot(
+                task,
+                &format!("messages mentioning '{}' over time", message_pattern),
+                &message_counts,
+                &stats::detect_bursts(&message_counts, burst_factor),
+                "bursts",
+            )?;
+            to_values(&message_counts)
+        }
+        plot::Metric::ActiveHoursWindow {
+            ref channel_pattern,
+            coverage,
+        } => {
+            let messages_to_plot = find_messages(messages, task, channel_pattern, |_| true);
+            // Time zones are checked when validating the task
+            let time_zone = task.time_zone().unwrap_or(Tz::UTC);
+            let hour_counts = count_by_hour(&messages_to_plot, &time_zone);
+            let coverage = coverage.unwrap_or(DEFAULT_ACTIVE_HOURS_COVERAGE);
+            let window = stats::active_hours_window(&hour_counts, coverage);
+            match window {
+                Some((start, end)) => println!(
+                    "Active hours of channels matching '{}' (covering {:.0}% of messages): {:02}:00-{:02}:59 {}.",
+                    channel_pattern,
+                    coverage * 100.0,
+                    start,
+                    end,
+                    time_zone.name()
+                ),
+                None => println!("No messages in channels matching '{}'.", channel_pattern),
+            }
+            let labeled_counts: Vec<(String, usize)> = hour_counts
+                .iter()
+                .enumerate()
+                .map(|(hour, count)| (format!("{:02}", hour), *count))
+                .collect();
+            let in_window: Vec<bool> = (0..24)
+                .map(|hour| {
+                    window.is_some_and(|(start, end)| stats::hour_in_window(hour, start, end))
+                })
+                .collect();
+            plot::highlighted_plot(
+                task,
+                &format!(
+                    "messages per hour of day ({}) in channels matching '{}'",
+                    time_zone.name(),
+                    channel_pattern
+                ),
+                &labeled_counts,
+                &in_window,
+                "active hours",
+            )?;
+            to_values(&labeled_counts)
+        }
+        plot::Metric::DecayHalfLife {
+            ref channel_pattern,
+            ref message_pattern,
+        } => {
+            let message_counts =
+                filter_and_count_messages(messages, task, channel_pattern, message_pattern);
+            let counts: Vec<usize> = message_counts.iter().map(|(_, count)| *count).collect();
+            let decay = stats::half_life(&counts);
+            match decay {
+                Some((peak, Some(half_life))) => println!(
+                    "Messages mentioning '{}' peaked at {} and halved {} buckets later.",
+                    message_pattern, message_counts[peak].0, half_life
+                ),
+                Some((peak, None)) => println!(
+                    "Messages mentioning '{}' peaked at {} and have not halved since.",
+                    message_pattern, message_counts[peak].0
+                ),
+                None => println!("No messages mentioning '{}'.", message_pattern),
+            }
+            let is_peak_or_half: Vec<bool> = (0..counts.len())
+                .map(|index| {
+                    decay.is_some_and(|(peak, half_life)| {
+                        index == peak || half_life.is_some_and(|half_life| index == peak + half_life)
+                    })
+                })
+                .collect();
+            plot::highlighted_plot(
+                task,
+                &format!("messages mentioning '{}'", message_pattern),
+                &message_counts,
+                &is_peak_or_half,
+                "decay",
+            )?;
+            to_values(&message_counts)
+        }
+        plot::Metric::AverageThreadParticipants {
+            ref channel_pattern,
+        } => {
+            let participants = thread_participants(messages, task, channel_pattern);
+            plot_averages(task, "average thread participants", participants)?
+        }
+        plot::Metric::CumulativeUsers {
+            ref channel_pattern,
+        } => {
+            let user_counts = count_cumulative_users(messages, task, channel_pattern);
+            plot_counts(
+                task,
+                &format!("cumulative users in channels matching '{}'", channel_pattern),
+                &user_counts,
+            )?
+        }
+        plot::Metric::CumulativeCount {
+            ref channel_pattern,
+            ref message_pattern,
+        } => {
+            let message_counts = filter_and_count_messages(messages, task, channel_pattern, message_pattern);
+            let totals = running_totals(&message_counts);
+            plot::line_plot(task, &format!("total messages mentioning '{}'", message_pattern), &totals)?;
+            totals
+        }
+        plot::Metric::MembershipGrowth {
+            ref channel_pattern,
+        } => {
+            let members = count_members_over_time(messages, task, channel_pattern);
+            plot::line_plot(task, &format!("members of channels matching '{}'", channel_pattern), &members)?;
+            members
+        }
+        plot::Metric::MaxReplyDepth {
+            ref channel_pattern,
+        } => {
+            let depths = max_reply_depths(messages, task, channel_pattern);
+            plot_averages(task, "average maximal reply depth of threads", depths)?
+        }
+        plot::Metric::ParticipationGini {
+            ref channel_pattern,
+            ref message_pattern,
+        } => {
+            let matching_messages =
+                find_matching_messages(messages, task, channel_pattern, message_pattern);
+            println!(
+                "Gini coefficient of participation for messages matching '{}': {:.3}",
+                message_pattern,
+                participation_gini(&matching_messages)
+            );
+            let coefficients: Vec<(String, f64)> = group_by_time(&matching_messages, task)
+                .into_iter()
+                .map(|(label, bucket)| (label, participation_gini(&bucket)))
+                .collect();
+            plot::line_plot(
+                task,
+                &format!(
+                    "participation inequality (Gini) for messages mentioning '{}'",
+                    message_pattern
+                ),
+                &coefficients,
+            )?;
+            coefficients
+        }
+        plot::Metric::EmojiOnlyMessageCount {
+            ref channel_pattern,
+        } => {
+            let messages_to_plot = find_messages(messages, task, channel_pattern, |x| {
+                x.message.is_emoji_only()
+            });
+            println!("Found {} emoji-only messages.", messages_to_plot.len());
+            let message_counts = group_messages_by_time(&messages_to_plot, task);
+            plot_channel_counts(
+                task,
+                messages,
+                channel_pattern,
+                "emoji-only messages",
+                &message_counts,
+            )?
+        }
+        plot::Metric::ReactionCount {
+            ref channel_pattern,
+            ref emoji,
+        } => {
+            let reaction_counts = count_reactions(messages, task, channel_pattern, emoji);
+            plot_channel_counts(
+                task,
+                messages,
+                channel_pattern,
+                &format!("'{}' reactions", emoji),
+                &reaction_counts,
+            )?
+        }
+        plot::Metric::CrossPostCount {
+            ref message_pattern,
+            window_minutes,
+        } => {
+            let cross_post_counts = count_cross_posts(
+                messages,
+                task,
+                message_pattern,
+                window_minutes.unwrap_or(DEFAULT_CROSS_POST_WINDOW_MINUTES),
+            );
+            plot_counts(
+                task,
+                &format!("cross-posted messages mentioning '{}'", message_pattern),
+                &cross_post_counts,
+            )?
+        }
+        plot::Metric::ActivityHeatmap {
+            ref channel_pattern,
+        } => {
+            let messages_to_plot = find_messages(messages, task, channel_pattern, |_| true);
+            let matrix = count_by_weekday_and_hour(&messages_to_plot, task);
+            let weekdays: Vec<String> = WEEKDAYS.iter().map(|weekday| weekday.to_string()).collect();
+            let hours: Vec<String> = (0..24).map(|hour| format!("{:02}", hour)).collect();
+            let rows: Vec<Vec<usize>> = matrix.iter().map(|counts| counts.to_vec()).collect();
+            plot::heatmap_plot(task, "activity per weekday and hour", &weekdays, &hours, &rows)?;
+            weekdays
+                .iter()
+                .zip(&rows)
+                .flat_map(|(weekday, counts)| {
+                    hours
+                        .iter()
+                        .zip(counts)
+                        .map(move |(hour, count)| (format!("{}/{}", weekday, hour), *count as f64))
+                })
+                .collect()
+        }
+        plot::Metric::ThreadReplyCount {
+            ref channel_pattern,
+        } => {
+            let replies = find_messages(messages, task, channel_pattern, |x| x.message.is_thread_reply());
+            println!("Found {} thread replies.", replies.len());
+            let reply_counts = group_messages_by_time(&replies, task);
+            plot_channel_counts(task, messages, channel_pattern, "thread replies", &reply_counts)?
+        }
+        plot::Metric::UniqueUserCount {
+            ref channel_pattern,
+            ref message_pattern,
+        } => {
+            let messages_to_plot =
+                find_matching_messages(messages, task, channel_pattern, message_pattern);
+            let user_counts = count_users_by_time(&messages_to_plot, task);
+            plot_channel_counts(
+                task,
+                messages,
+                channel_pattern,
+                &format!("users mentioning '{}'", message_pattern),
+                &user_counts,
+            )?
+        }
+        plot::Metric::ReactorPosterOverlap {
+            ref channel_pattern,
+        } => {
+            let overlaps = reactor_poster_overlaps(messages, task, channel_pattern);
+            plot::line_plot(task, "overlap of posters and reactors", &overlaps)?;
+            overlaps
+        }
+        plot::Metric::WeeklyActiveUsers {
+            ref channel_pattern,
+            ref message_pattern,
+        } => {
+            let user_counts =
+                count_weekly_active_users(messages, task, channel_pattern, message_pattern.as_deref());
+            plot_counts(
+                task,
+                &format!("weekly active users in channels matching '{}'", channel_pattern),
+                &user_counts,
+            )?
+        }
+        plot::Metric::MessageLengthPercentile {
+            ref channel_pattern,
+            ref message_pattern,
+            percentile,
+        } => {
+            let matching_messages =
+                find_matching_messages(messages, task, channel_pattern, message_pattern);
+            let lengths: Vec<(String, f64)> = group_by_time(&matching_messages, task)
+                .into_iter()
+                .map(|(label, bucket)| {
+                    let bucket_lengths: Vec<f64> = bucket
+                        .iter()
+                        .map(|x| x.message.text.chars().count() as f64)
+                        .collect();
+                    (label, stats::percentile(&bucket_lengths, percentile))
+                })
+                .collect();
+            plot::line_plot(
+                task,
+                &format!(
+                    "p{} message length of messages mentioning '{}'",
+                    percentile, message_pattern
+                ),
+                &lengths,
+            )?;
+            lengths
+        }
+        plot::Metric::MessageLengthDistribution {
+            ref channel_pattern,
+            ref message_pattern,
+            unit,
+        } => {
+            let unit = unit.unwrap_or_default();
+            let unit_name = match unit {
+                plot::LengthUnit::Characters => "characters",
+                plot::LengthUnit::Words => "words",
+            };
+            let matching_messages =
+                find_matching_messages(messages, task, channel_pattern, message_pattern);
+            let distributions: Vec<(String, Vec<f64>)> = group_by_time(&matching_messages, task)
+                .into_iter()
+                .map(|(label, bucket)| {
+                    let bucket_lengths = bucket.iter().map(|x| message_length(x, unit) as f64).collect();
+                    (label, bucket_lengths)
+                })
+                .collect();
+            plot::box_plot(
+                task,
+                &format!(
+                    "message length (in {}) of messages mentioning '{}'",
+                    unit_name, message_pattern
+                ),
+                &distributions,
+            )?;
+            distributions
+                .iter()
+                .map(|(label, lengths)| (label.clone(), stats::median(lengths)))
+                .collect()
+        }
+        plot::Metric::PatternGroupCount {
+            ref channel_pattern,
+            ref pattern_groups,
+        } => {
+            let pattern_groups: Vec<(String, Vec<String>)> =
+                pattern_groups.clone().into_iter().collect();
+            let group_counts = count_pattern_groups(messages, task, channel_pattern, &pattern_groups);
+            plot_named_counts(task, "messages per pattern group", group_counts)?
+        }
+        plot::Metric::TermAdoption {
+            ref channel_pattern,
+            ref terms,
+        } => {
+            let term_groups: Vec<(String, Vec<String>)> = terms
+                .iter()
+                .map(|term| (term.clone(), vec![term.clone()]))
+                .collect();
+            let term_counts = count_pattern_groups(messages, task, channel_pattern, &term_groups);
+            plot_named_counts(task, "mentions per term", term_counts)?
+        }
+        plot::Metric::OnboardingActivity {
+            ref channel_pattern,
+            days,
+        } => {
+            let days = days.unwrap_or(DEFAULT_ONBOARDING_DAYS);
+            let averages = average_onboarding_activity(messages, task, channel_pattern, days);
+            plot::line_plot(
+                task,
+                &format!("average messages in the first {} days, per cohort of new users", days),
+                &averages,
+            )?;
+            averages
+        }
+        plot::Metric::MessageTypeComposition {
+            ref channel_pattern,
+        } => {
+            let category_counts = count_message_types(messages, task, channel_pattern);
+            plot::pie_plot(
+                task,
+                &format!("message types in channels matching '{}'", channel_pattern),
+                &category_counts,
+            )?;
+            proportions(&category_counts)
+        }
+        plot::Metric::TopPosters {
+            ref channel_pattern,
+            top_n,
+        } => {
+            let top_posters = count_top_posters(messages, task, channel_pattern, top_n);
+            plot::bar_plot(
+                task,
+                &format!("top posters in channels matching '{}'", channel_pattern),
+                &top_posters,
+            )?;
+            to_values(&top_posters)
+        }
+        plot::Metric::TopChannels {
+            ref message_pattern,
+            top_n,
+        } => {
+            let top_channels = count_top_channels(messages, task, message_pattern, top_n);
+            plot::bar_plot(
+                task,
+                &format!("top channels with messages mentioning '{}'", message_pattern),
+                &top_channels,
+            )?;
+            to_values(&top_channels)
+        }
+        plot::Metric::WordFrequency {
+            ref channel_pattern,
+            top_n,
+            ref stopwords,
+        } => {
+            let top_words = count_top_words(messages, task, channel_pattern, top_n, stopwords.as_deref());
+            plot::bar_plot(
+                task,
+                &format!("most frequent words in channels matching '{}'", channel_pattern),
+                &top_words,
+            )?;
+            to_values(&top_words)
+        }
+        plot::Metric::TopReactedMessages {
+            ref channel_pattern,
+            top_n,
+        } => {
+            let top_messages = top_reacted_messages(messages, task, channel_pattern, top_n);
+            let rows: Vec<Vec<String>> = top_messages
+                .iter()
+                .map(|x| {
+                    vec![
+                        snippet(&x.message.text, SNIPPET_LENGTH),
+                        x.channel.clone(),
+                        x.message.time().map(|time| time.to_rfc3339()).unwrap_or_default(),
+                        x.message.reaction_count().to_string(),
+                    ]
+                })
+                .collect();
+            plot::table_output(
+                task,
+                "most reacted messages",
+                &["text", "channel", "time", "reactions"],
+                &rows,
+            )?;
+            top_messages
+                .iter()
+                .map(|x| {
+                    let label = format!("{}/{}", x.channel, x.message.ts());
+                    (label, x.message.reaction_count() as f64)
+                })
+                .collect()
+        }
+        plot::Metric::TimeToFirstReaction {
+            ref channel_pattern,
+        } => {
+            let medians = median_time_to_first_reaction(messages, task, channel_pattern)?;
+            plot::line_plot(task, "median minutes to first reaction", &medians)?;
+            medians
+        }
+        plot::Metric::DuplicateMessageCount {
+            ref channel_pattern,
+            window_minutes,
+        } => {
+            let duplicate_counts = count_duplicate_messages(
+                messages,
+                task,
+                channel_pattern,
+                window_minutes.unwrap_or(DEFAULT_DUPLICATE_WINDOW_MINUTES),
+            );
+            plot_counts(task, "duplicate messages", &duplicate_counts)?
+        }
+        plot::Metric::CoOccurrenceCount {
+            ref channel_pattern,
+            ref pattern_a,
+            ref pattern_b,
+        } => {
+            let message_counts =
+                count_co_occurrences(messages, task, channel_pattern, pattern_a, pattern_b);
+            plot_counts(
+                task,
+                &format!("messages mentioning both '{}' and '{}'", pattern_a, pattern_b),
+                &message_counts,
+            )?
+        }
+        plot::Metric::LongestSilence {
+            ref channel_pattern,
+        } => {
+            let silences = longest_silences(messages, task, channel_pattern);
+            let longest = silences.iter().map(|(_, days)| *days).fold(0.0, f64::max);
+            for (channel, days) in &silences {
+                println!("Longest silence in channel '{}': {:.1} days.", channel, days);
+            }
+            let whole_days: Vec<(String, usize)> = silences
+                .iter()
+                .map(|(channel, days)| (channel.clone(), days.round() as usize))
+                .collect();
+            let is_longest: Vec<bool> = silences.iter().map(|(_, days)| *days == longest).collect();
+            plot::highlighted_plot(
+                task,
+                "longest silence (in days) per channel",
+                &whole_days,
+                &is_longest,
+                "longest",
+            )?;
+            silences
+        }
+    };
+    Ok(series)
+}
+
+/// The default fraction of messages that the `ActiveHoursWindow` needs to cover.
+const DEFAULT_ACTIVE_HOURS_COVERAGE: f64 = 0.9;
+
+/// Checks whether the hour lies in the quiet hours from `start_hour` until before `end_hour`,
+/// which wrap around midnight if `start_hour` is larger (e.g. 20 to 8).
+fn is_quiet_hour(hour: u32, start_hour: u32, end_hour: u32) -> bool {
+    if start_hour <= end_hour {
+        (start_hour..end_hour).contains(&hour)
+    } else {
+        hour >= start_hour || hour < end_hour
+    }
+}
+
+/// The default time window (in minutes) in which identical posts count as a cross-post.
+const DEFAULT_CROSS_POST_WINDOW_MINUTES: u32 = 60;
+
+/// Counts messages posted (near-)identically to several channels within the given time window.
+///
+/// Texts are compared case-insensitively and with normalized whitespace. Each cross-post
+/// is counted once, in the bucket of its first post.
+fn count_cross_posts(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    message_pattern: &str,
+    window_minutes: u32,
+) -> Vec<(String, usize)> {
+    let window = TimeDelta::minutes(window_minutes as i64);
+    // Groups of identical posts: (time of first post, channels)
+    let mut groups: HashMap<String, Vec<(DateTime<Utc>, HashSet<_>)>> = HashMap::new();
+    for message in find_matching_messages(messages, task, "", message_pattern) {
+        let Some(time) = message.message.time() else {
+            continue;
+        };
+        let text_groups = groups.entry(normalize_text(&message.message.text)).or_default();
+        match text_groups.last_mut() {
+            Some((start, channels)) if time - *start <= window => {
+                channels.insert(message.channel.as_str());
+            }
+            _ => text_groups.push((time, HashSet::from([message.channel.as_str()]))),
+        }
+    }
+    let mut cross_post_counts: BTreeMap<String, usize> = BTreeMap::new();
+    for (start, _) in groups
+        .values()
+        .flatten()
+        .filter(|(_, channels)| channels.len() > 1)
+    {
+        *cross_post_counts.entry(time_label(start, task)).or_insert(0) += 1;
+    }
+    println!(
+        "Found {} cross-posts mentioning '{}'.",
+        cross_post_counts.values().sum::<usize>(),
+        message_pattern
+    );
+    cross_post_counts.into_iter().collect()
+}
+
+/// Finds the longest gap (in days) between consecutive messages for each matching channel.
+///
+/// Channels with a single message have a gap of zero days.
+fn longest_silences(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+) -> Vec<(String, f64)> {
+    let mut channel_times: BTreeMap<&str, Vec<DateTime<Utc>>> = BTreeMap::new();
+    for message in find_messages(messages, task, channel_pattern, |_| true) {
+        channel_times
+            .entry(message.channel.as_str())
+            .or_default()
+            .extend(message.message.time());
+    }
+    channel_times
+        .into_iter()
+        .map(|(channel, mut times)| {
+            times.sort();
+            let longest_gap = times
+                .windows(2)
+                .map(|pair| pair[1] - pair[0])
+                .max()
+                .unwrap_or_default();
+            (channel.to_string(), longest_gap.num_seconds() as f64 / 86400.0)
+        })
+        .collect()
+}
+
+/// The default number of days after their first message in which the activity of new users is counted.
+const DEFAULT_ONBOARDING_DAYS: u32 = 7;
+
+/// Counts each user's messages within the given number of days after their first message (which serves
+/// as a proxy for the join date), and averages these counts per cohort, i.e. the time bucket of the first message.
+fn average_onboarding_activity(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    days: u32,
+) -> Vec<(String, f64)> {
+    let mut user_times: HashMap<&str, Vec<DateTime<Utc>>> = HashMap::new();
+    for message in find_messages(messages, task, channel_pattern, |_| true) {
+        if let Some(user) = message.message.user() {
+            user_times.entry(user).or_default().extend(message.message.time());
+        }
+    }
+    let onboarding_period = TimeDelta::days(days as i64);
+    let activities: Vec<(String, f64)> = user_times
+        .values()
+        .map(|times| {
+            let first_time = *times.iter().min().expect("Users have at least one message");
+            let count = times
+                .iter()
+                .filter(|time| **time - first_time < onboarding_period)
+                .count();
+            (time_label(&first_time, task), count as f64)
+        })
+        .collect();
+    average_per_bucket(activities)
+}
+
+/// The message type categories of `MessageTypeComposition`, in the order they are plotted.
+const MESSAGE_TYPES: [&str; 5] = ["regular", "joins", "bots", "file shares", "other events"];
+
+/// Categorizes a message by its subtype into one of the `MESSAGE_TYPES`.
+fn message_type(message: &MessageInChannel) -> &'static str {
+    if message.message.is_bot() {
+        "bots"
+    } else if message.message.has_files() {
+        "file shares"
+    } else {
+        match message.message.subtype() {
+            None | Some("thread_broadcast") => "regular",
+            Some("channel_join") => "joins",
+            Some(_) => "other events",
+        }
+    }
+}
+
+/// Counts the messages per message type, leaving out types without messages.
+fn count_message_types(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+) -> Vec<(String, usize)> {
+    let messages_to_count = find_messages(messages, task, channel_pattern, |_| true);
+    MESSAGE_TYPES
+        .iter()
+        .map(|category| {
+            let count = messages_to_count
+                .iter()
+                .filter(|x| message_type(x) == *category)
+                .count();
+            (category.to_string(), count)
+        })
+        .filter(|(_, count)| *count > 0)
+        .collect()
+}
+
+/// Converts counts into their proportions of the total count.
+fn proportions(counts: &[(String, usize)]) -> Vec<(String, f64)> {
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+    counts
+        .iter()
+        .map(|(label, count)| (label.clone(), *count as f64 / total.max(1) as f64))
+        .collect()
+}
+
+/// The maximal number of characters of message texts in tables.
+const SNIPPET_LENGTH: usize = 80;
+
+/// Ranks the messages by their total number of reactions and returns the `top_n` ones
+/// (earlier messages first among those with equally many reactions).
+fn top_reacted_messages<'a>(
+    messages: &'a [MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    top_n: usize,
+) -> Vec<&'a MessageInChannel> {
+    let mut reacted_messages = find_messages(messages, task, channel_pattern, |x| {
+        x.message.has_reactions()
+    });
+    reacted_messages.sort_by_key(|x| {
+        (std::cmp::Reverse(x.message.reaction_count()), x.message.time())
+    });
+    reacted_messages.truncate(top_n);
+    reacted_messages
+}
+
+/// Shortens a text to at most `max_length` characters (on a single line), marking omissions with '…'.
+fn snippet(text: &str, max_length: usize) -> String {
+    let single_line = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if single_line.chars().count() <= max_length {
+        single_line
+    } else {
+        single_line.chars().take(max_length - 1).collect::<String>() + "…"
+    }
+}
+
+/// Counts the messages that contain both patterns, per time bucket.
+fn count_co_occurrences(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    pattern_a: &str,
+    pattern_b: &str,
+) -> Vec<(String, usize)> {
+    let (matcher_a, matcher_b) = (matcher(task, pattern_a), matcher(task, pattern_b));
+    let messages_to_plot = find_messages(messages, task, channel_pattern, |x| {
+        x.message.contains(&matcher_a) && x.message.contains(&matcher_b)
+    });
+    println!(
+        "Found {} messages mentioning both '{}' and '{}'.",
+        messages_to_plot.len(),
+        pattern_a,
+        pattern_b
+    );
+    group_messages_by_time(&messages_to_plot, task)
+}
+
+/// The default time window (in minutes) in which a user's repeated message counts as a duplicate.
+const DEFAULT_DUPLICATE_WINDOW_MINUTES: u32 = 10;
+
+/// Counts messages that repeat the exact text of the same user's previous message with that text
+/// within the given time window (a proxy for spam or flooding), in the bucket of the repetition.
+fn count_duplicate_messages(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    window_minutes: u32,
+) -> Vec<(String, usize)> {
+    let window = TimeDelta::minutes(window_minutes as i64);
+    let mut candidates = find_messages(messages, task, channel_pattern, |x| {
+        x.message.user().is_some() && !x.message.text.is_empty()
+    });
+    candidates.sort_by_key(|x| x.message.time());
+    let mut last_posts: HashMap<(&str, &str), DateTime<Utc>> = HashMap::new();
+    let mut duplicates: Vec<&MessageInChannel> = Vec::new();
+    for message in candidates {
+        let Some(time) = message.message.time() else {
+            continue;
+        };
+        let key = (message.message.user().unwrap_or_default(), message.message.text.as_str());
+        if let Some(last_time) = last_posts.insert(key, time)
+            && time - last_time <= window
+        {
+            duplicates.push(message);
+        }
+    }
+    println!("Found {} duplicate messages.", duplicates.len());
+    group_messages_by_time(&duplicates, task)
+}
+
+/// Normalizes a text for comparisons, i.e. lower-cases it and collapses all whitespace.
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The labels of the days of the week, starting with Monday.
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Counts the messages per day of the week (starting with Monday) and hour of the day, in the task's time zone.
+fn count_by_weekday_and_hour(messages: &[&MessageInChannel], task: &PlotTask) -> [[usize; 24]; 7] {
+    // Time zones are checked when validating the task
+    let time_zone = task.time_zone().unwrap_or(Tz::UTC);
+    let mut counts = [[0; 24]; 7];
+    for time in messages.iter().filter_map(|x| x.message.time()) {
+        let local_time = time.with_timezone(&time_zone);
+        counts[local_time.weekday().num_days_from_monday() as usize][local_time.hour() as usize] += 1;
+    }
+    counts
+}
+
+/// Counts the messages per hour of the day in the given time zone.
+fn count_by_hour(messages: &[&MessageInChannel], time_zone: &Tz) -> [usize; 24] {
+    let mut hour_counts = [0; 24];
+    for time in messages.iter().filter_map(|x| x.message.time()) {
+        hour_counts[time.with_timezone(time_zone).hour() as usize] += 1;
+    }
+    hour_counts
+}
+
+/// Converts a count series into a series of values.
+fn to_values(message_counts: &[(String, usize)]) -> Vec<(String, f64)> {
+    message_counts
+        .iter()
+        .map(|(label, count)| (label.clone(), *count as f64))
+        .collect()
+}
+
+/// Plots a count series, either directly or transformed as configured in the task.
+/// Returns the plotted series.
+fn plot_counts(
+    task: &PlotTask,
+    description: &str,
+    message_counts: &[(String, usize)],
+) -> Result<Vec<(String, f64)>, Box<dyn std::error::Error>> {
+    match task.zscore_window {
+        Some(window) => {
+            let z_scores = stats::calculate_z_scores(message_counts, window);
+            plot::line_plot(task, &format!("z-scores of {}", description), &z_scores)?;
+            Ok(z_scores)
+        }
+        None => {
+            plot::counter_plot(task, description, message_counts)?;
+            Ok(to_values(message_counts))
+        }
+    }
+}
+
+/// Plots message counts of the channels matching the pattern like `plot_counts`,
+/// or divided by the number of members of these channels if the task's `per_member` option is set.
+fn plot_channel_counts(
+    task: &PlotTask,
+    messages: &[MessageInChannel],
+    channel_pattern: &str,
+    description: &str,
+    message_counts: &[(String, usize)],
+) -> Result<Vec<(String, f64)>, Box<dyn std::error::Error>> {
+    if !task.per_member.unwrap_or(false) {
+        return plot_counts(task, description, message_counts);
+    }
+    let members = count_channel_members(task, messages, channel_pattern)?;
+    let counts_per_member: Vec<(String, f64)> = message_counts
+        .iter()
+        .map(|(label, count)| (label.clone(), *count as f64 / members as f64))
+        .collect();
+    plot::line_plot(task, &format!("{} per member", description), &counts_per_member)?;
+    Ok(counts_per_member)
+}
+
+/// Sums up the members of all channels (with messages) matching the pattern.
+fn count_channel_members(
+    task: &PlotTask,
+    messages: &[MessageInChannel],
+    channel_pattern: &str,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let channel_members = task
+        .channel_members
+        .as_ref()
+        .filter(|channel_members| !channel_members.is_empty())
+        .ok_or("Counting per member requires channel members, but the export has none in channels.json.")?;
+    let channel_matcher = matcher(task, channel_pattern);
+    let channels: BTreeSet<&str> = messages
+        .iter()
+        .filter(|x| channel_matcher.is_match(&x.channel) && !task.excluded_channels.contains(&x.channel))
+        .map(|x| x.channel.as_str())
+        .collect();
+    let mut members = 0;
+    for channel in channels {
+        members += channel_members
+            .get(channel)
+            .ok_or_else(|| format!("The members of channel '{}' are unknown.", channel))?;
+    }
+    if members == 0 {
+        return Err(format!("No members found in channels matching '{}'.", channel_pattern).into());
+    }
+    Ok(members)
+}
+
+/// Plots several named count series as lines and returns them as one series,
+/// where each value is labeled with the series name and the time bucket (e.g. `infra/2025-01`).
+fn plot_named_counts(
+    task: &PlotTask,
+    description: &str,
+    named_counts: Vec<(String, Vec<(String, usize)>)>,
+) -> Result<Vec<(String, f64)>, Box<dyn std::error::Error>> {
+    let named_series: Vec<(String, Vec<(String, f64)>)> = named_counts
+        .into_iter()
+        .map(|(name, counts)| (name, to_values(&counts)))
+        .collect();
+    plot::multi_line_plot(task, description, &named_series)?;
+    Ok(flatten_named_series(named_series))
+}
+
+/// Combines several named series into one, labeling each value with the series name and the time bucket.
+fn flatten_named_series(named_series: Vec<(String, Vec<(String, f64)>)>) -> Vec<(String, f64)> {
+    named_series
+        .into_iter()
+        .flat_map(|(name, series)| {
+            series
+                .into_iter()
+                .map(move |(label, value)| (format!("{}/{}", name, label), value))
+        })
+        .collect()
+}
+
+/// Checks the general, task-level filters that apply to all metrics (including a valid timestamp).
+fn is_included(task: &PlotTask, message: &MessageInChannel) -> bool {
+    message.message.time().is_some()
+        && (task.include_deleted.unwrap_or(false) || !message.message.is_deleted())
+        && (task.include_thread_replies.unwrap_or(true) || !message.message.is_thread_reply())
+        && !(task.exclude_bots.unwrap_or(false) && message.message.is_bot())
+        && !task.excluded_channels.contains(&message.channel)
+        && task
+            .min_message_length
+            .is_none_or(|min_length| message.message.text.chars().count() >= min_length)
+}
+
+thread_local! {
+    /// The messages selected by `find_messages` while a task is computed on this thread, if they are recorded
+    /// (see `process_task_counting_matches`). Tasks are computed on a single thread each, without nested parallelism.
+    static SELECTED_MESSAGES: RefCell<Option<HashSet<*const MessageInChannel>>> = const { RefCell::new(None) };
+}
+
+/// Returns the messages in matching channels that pass the task-level filters and the given predicate.
+///
+/// All metrics select their messages through this, so the selected messages are recorded for the task's match count.
+fn find_messages<'a, P>(
+    messages: &'a [MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    predicate: P,
+) -> Vec<&'a MessageInChannel>
+where
+    P: Fn(&MessageInChannel) -> bool,
+{
+    let channel_matcher = matcher(task, channel_pattern);
+    let user_matcher = user_matcher(task);
+    let found_messages: Vec<&MessageInChannel> = messages
+        .iter()
+        .filter(|x| {
+            channel_matcher.is_match(&x.channel)
+                && is_included(task, x)
+                && is_by_matching_user(x, user_matcher.as_ref())
+                && predicate(x)
+        })
+        .collect();
+    SELECTED_MESSAGES.with(|selected| {
+        if let Some(selected) = selected.borrow_mut().as_mut() {
+            selected.extend(found_messages.iter().map(|x| std::ptr::from_ref(*x)));
+        }
+    });
+    found_messages
+}
+
+/// Compiles the task's user pattern, if it has one (see `matcher`).
+fn user_matcher(task: &PlotTask) -> Option<slack::Matcher> {
+    task.user_pattern.as_deref().map(|pattern| matcher(task, pattern))
+}
+
+/// Checks whether the ID or the display name of the message's user matches, if there is a matcher at all.
+fn is_by_matching_user(message: &MessageInChannel, user_matcher: Option<&slack::Matcher>) -> bool {
+    user_matcher.is_none_or(|user_matcher| {
+        message.message.user().is_some_and(|user| user_matcher.is_match(user))
+            || message.user_name.as_deref().is_some_and(|name| user_matcher.is_match(name))
+    })
+}
+
+/// Compiles a pattern according to the task's match mode.
+///
+/// Panics if the pattern is invalid, which `process_task_counting_matches` checks beforehand.
+fn matcher(task: &PlotTask, pattern: &str) -> slack::Matcher {
+    task.matcher(pattern).expect("Patterns need to be validated before processing.")
+}
+
+fn find_matching_messages<'a>(
+    messages: &'a [MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    message_pattern: &str,
+) -> Vec<&'a MessageInChannel> {
+    let message_matcher = matcher(task, message_pattern);
+    let matching_messages = find_messages(messages, task, channel_pattern, |x| {
+        x.message.contains(&message_matcher)
+    });
+    println!("Found {} messages matching '{}'.", matching_messages.len(), message_pattern);
+    matching_messages
+}
+
+/// Writes the precise timestamps of the messages matching each message pattern of the task's metric
+/// (see `plot::write_timestamps`).
+fn write_matching_timestamps(
+    task: &PlotTask,
+    messages: &[MessageInChannel],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let channel_pattern = task.metric.channel_pattern().unwrap_or("");
+    for message_pattern in task.metric.message_patterns() {
+        let message_matcher = matcher(task, message_pattern);
+        let times: Vec<DateTime<Utc>> = find_messages(messages, task, channel_pattern, |x| {
+            x.message.contains(&message_matcher)
+        })
+        .iter()
+        .filter_map(|x| x.message.time_precise())
+        .collect();
+        plot::write_timestamps(task, message_pattern, &times)?;
+    }
+    Ok(())
+}
+
+fn filter_and_count_messages(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    message_pattern: &str,
+) -> Vec<(String, usize)> {
+    let messages_to_plot = find_countable_messages(messages, task, channel_pattern, message_pattern);
+    group_messages_by_time(&messages_to_plot, task)
+}
+
+/// Finds the matching messages, leaving out those with subtypes excluded by the task.
+fn find_countable_messages<'a>(
+    messages: &'a [MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    message_pattern: &str,
+) -> Vec<&'a MessageInChannel> {
+    let excluded_subtypes = task.excluded_subtypes();
+    let message_matcher = matcher(task, message_pattern);
+    // Subtypes are filtered while finding the messages, so that only countable ones are recorded as matches
+    let countable_messages = find_messages(messages, task, channel_pattern, |x| {
+        x.message.contains(&message_matcher) && x.message.is_countable(&excluded_subtypes)
+    });
+    println!("Found {} countable messages matching '{}'.", countable_messages.len(), message_pattern);
+    countable_messages
+}
+
+/// Counts matching messages per time bucket for each of the `top_n` users with the most matching messages,
+/// named by their display names if known (see `count_messages_by_series`).
+fn filter_and_count_messages_by_user(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    message_pattern: &str,
+    top_n: usize,
+) -> Vec<(String, Vec<(String, usize)>)> {
+    let messages_to_plot = find_countable_messages(messages, task, channel_pattern, message_pattern);
+    count_messages_by_series(&messages_to_plot, task, |x| x.user_display_name(), top_n)
+}
+
+/// Counts matching messages per time bucket for each matching channel (see `count_messages_by_series`).
+fn filter_and_count_messages_by_channel(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    message_pattern: &str,
+) -> Vec<(String, Vec<(String, usize)>)> {
+    let messages_to_plot = find_countable_messages(messages, task, channel_pattern, message_pattern);
+    count_messages_by_series(&messages_to_plot, task, |x| Some(x.channel.as_str()), usize::MAX)
+}
+
+/// Counts the messages of the `top_n` users with the most messages, named by their display names if known.
+fn count_top_posters(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    top_n: usize,
+) -> Vec<(String, usize)> {
+    let messages_to_plot = find_countable_messages(messages, task, channel_pattern, "");
+    count_series_totals(&messages_to_plot, |x| x.user_display_name())
+        .into_iter()
+        .take(top_n)
+        .map(|(user, count)| (user.to_string(), count))
+        .collect()
+}
+
+/// Counts the matching messages of the `top_n` channels with the most of them.
+fn count_top_channels(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    message_pattern: &str,
+    top_n: usize,
+) -> Vec<(String, usize)> {
+    let messages_to_plot = find_countable_messages(messages, task, "", message_pattern);
+    count_series_totals(&messages_to_plot, |x| Some(x.channel.as_str()))
+        .into_iter()
+        .take(top_n)
+        .map(|(channel, count)| (channel.to_string(), count))
+        .collect()
+}
+
+/// Counts the words in the (plain) texts of the messages, ignoring stopwords (see `text::stopwords`),
+/// and returns the `top_n` most frequent ones, ordered by descending count and then alphabetically.
+fn count_top_words(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    top_n: usize,
+    custom_stopwords: Option<&[String]>,
+) -> Vec<(String, usize)> {
+    let stopwords = text::stopwords(custom_stopwords);
+    let mut word_counts: HashMap<String, usize> = HashMap::new();
+    for message in find_countable_messages(messages, task, channel_pattern, "") {
+        for word in text::tokenize(&message.message.plain_text()) {
+            if !stopwords.contains(&word) {
+                *word_counts.entry(word).or_insert(0) += 1;
+            }
+        }
+    }
+    let mut counts: Vec<(String, usize)> = word_counts.into_iter().collect();
+    counts.sort_by(|(word_a, count_a), (word_b, count_b)| count_b.cmp(count_a).then(word_a.cmp(word_b)));
+    counts.truncate(top_n);
+    counts
+}
+
+/// Returns the length of the message's text in the given unit.
+fn message_length(message: &MessageInChannel, unit: plot::LengthUnit) -> usize {
+    match unit {
+        plot::LengthUnit::Characters => message.message.text.chars().count(),
+        plot::LengthUnit::Words => text::tokenize(&message.message.plain_text()).len(),
+    }
+}
+
+/// Counts the messages per series (ignoring messages without a series), ordered by descending count and then by name.
+fn count_series_totals<'a, F>(messages_to_plot: &Vec<&'a MessageInChannel>, series_of: F) -> Vec<(&'a str, usize)>
+where
+    F: Fn(&'a MessageInChannel) -> Option<&'a str>,
+{
+    let mut series_totals: HashMap<&str, usize> = HashMap::new();
+    for series in messages_to_plot.iter().filter_map(|x| series_of(x)) {
+        *series_totals.entry(series).or_insert(0) += 1;
+    }
+    let mut totals: Vec<(&str, usize)> = series_totals.into_iter().collect();
+    totals.sort_by(|(series_a, count_a), (series_b, count_b)| count_b.cmp(count_a).then(series_a.cmp(series_b)));
+    totals
+}
+
+/// Counts the messages per time bucket for each of the `top_n` series (e.g. users) with the most messages,
+/// ordered by their total counts.
+///
+/// The messages of all other series (and those without a series) are counted as 'other', which is only included
+/// if there are any. All series share the labels of all messages.
+fn count_messages_by_series<'a, F>(
+    messages_to_plot: &Vec<&'a MessageInChannel>,
+    task: &PlotTask,
+    series_of: F,
+    top_n: usize,
+) -> Vec<(String, Vec<(String, usize)>)>
+where
+    F: Fn(&'a MessageInChannel) -> Option<&'a str>,
+{
+    const OTHER: &str = "other";
+    let top_series: Vec<&str> = count_series_totals(messages_to_plot, &series_of)
+        .into_iter().take(top_n).map(|(series, _)| series).collect();
+
+    let mut counts: HashMap<(&str, String), usize> = HashMap::new();
+    for message in messages_to_plot {
+        let series = series_of(message)
+            .filter(|series| top_series.contains(series))
+            .unwrap_or(OTHER);
+        *counts.entry((series, time_by_resolution(message, task))).or_insert(0) += 1;
+    }
+    let mut series_names = top_series;
+    if counts.keys().any(|(series, _)| *series == OTHER) {
+        series_names.push(OTHER);
+    }
+    let labels: Vec<String> = group_messages_by_time(messages_to_plot, task)
+        .into_iter()
+        .map(|(label, _)| label)
+        .collect();
+    series_names
+        .into_iter()
+        .map(|series| {
+            let series_counts = labels
+                .iter()
+                .map(|label| (label.clone(), counts.get(&(series, label.clone())).copied().unwrap_or(0)))
+                .collect();
+            (series.to_string(), series_counts)
+        })
+        .collect()
+}
+
+/// Counts messages that contain a URL matching the given pattern, ignoring the pattern in plain text.
+fn filter_and_count_linking_messages(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    url_pattern: &str,
+) -> Vec<(String, usize)> {
+    let url_matcher = matcher(task, url_pattern);
+    let messages_to_plot = find_messages(messages, task, channel_pattern, |x| {
+        x.message.links_to(&url_matcher)
+    });
+    println!("Found {} messages linking to '{}'.", messages_to_plot.len(), url_pattern);
+    group_messages_by_time(&messages_to_plot, task)
+}
+
+/// Counts the distinct users of each thread, labeled by the time bucket of the thread's root message.
+///
+/// Messages outside of threads count as threads with a single participant.
+fn thread_participants(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+) -> Vec<(String, f64)> {
+    let mut threads: HashMap<(&str, &str), (String, HashSet<&str>)> = HashMap::new();
+    for message in find_messages(messages, task, channel_pattern, |_| true) {
+        let Some(thread_time) = message.message.thread_time() else {
+            continue;
+        };
+        let (_, participants) = threads
+            .entry((message.channel.as_str(), message.message.thread_ts()))
+            .or_insert_with(|| (time_label(&thread_time, task), HashSet::new()));
+        participants.extend(message.message.user());
+    }
+    println!("Found {} threads in channels matching '{}'.", threads.len(), channel_pattern);
+    threads
+        .into_values()
+        .map(|(label, participants)| (label, participants.len().max(1) as f64))
+        .collect()
+}
+
+/// Finds the maximal reply depth of each thread, labeled by the time bucket of the thread's root message.
+///
+/// Replies refer to their parent via `thread_ts`, so a root message without replies has depth 0,
+/// and a regular Slack thread (root + replies) has depth 1. Deeper chains are followed where the export contains them.
+fn max_reply_depths(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+) -> Vec<(String, f64)> {
+    let messages_to_plot = find_messages(messages, task, channel_pattern, |_| true);
+    let parents: HashMap<(&str, &str), &str> = messages_to_plot
+        .iter()
+        .map(|x| ((x.channel.as_str(), x.message.ts()), x.message.thread_ts()))
+        .collect();
+    let mut max_depths: HashMap<(&str, &str), usize> = HashMap::new();
+    for message in &messages_to_plot {
+        let channel = message.channel.as_str();
+        let mut current = message.message.ts();
+        let mut depth: usize = 0;
+        while let Some(parent) = parents.get(&(channel, current)).copied()
+            && parent != current
+            && depth < parents.len()
+        {
+            current = parent;
+            depth += 1;
+        }
+        let max_depth = max_depths.entry((channel, current)).or_insert(0);
+        *max_depth = (*max_depth).max(depth);
+    }
+    println!("Found {} threads in channels matching '{}'.", max_depths.len(), channel_pattern);
+    max_depths
+        .into_iter()
+        .filter_map(|((_, root_ts), depth)| {
+            Some((time_label(&slack::parse_timestamp(root_ts)?, task), depth as f64))
+        })
+        .collect()
+}
+
+/// Calculates the Gini coefficient of the per-user message counts (ignoring messages without user).
+fn participation_gini(messages: &[&MessageInChannel]) -> f64 {
+    let mut user_counts: HashMap<&str, usize> = HashMap::new();
+    for user in messages.iter().filter_map(|x| x.message.user()) {
+        *user_counts.entry(user).or_insert(0) += 1;
+    }
+    let counts: Vec<f64> = user_counts.values().map(|count| *count as f64).collect();
+    stats::gini(&counts)
+}
+
+/// Counts the distinct users who posted up to (and including) each time bucket.
+fn count_cumulative_users(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+) -> Vec<(String, usize)> {
+    let messages_to_plot = find_messages(messages, task, channel_pattern, |_| true);
+    let mut users: HashSet<&str> = HashSet::new();
+    group_by_time(&messages_to_plot, task)
+        .into_iter()
+        .map(|(label, bucket)| {
+            users.extend(bucket.iter().filter_map(|x| x.message.user()));
+            (label, users.len())
+        })
+        .collect()
+}
+
+/// Counts the channel members at the end of each time bucket, as the running total of joins minus leaves
+/// (according to the `channel_join` and `channel_leave` messages), including empty buckets in between.
+fn count_members_over_time(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+) -> Vec<(String, f64)> {
+    let membership_changes = find_messages(messages, task, channel_pattern, |x| {
+        matches!(x.message.subtype(), Some("channel_join" | "channel_leave"))
     });
-    Ok(())
+    let mut net_changes: HashMap<String, i64> = HashMap::new();
+    for message in &membership_changes {
+        let change = if message.message.subtype() == Some("channel_join") { 1 } else { -1 };
+        *net_changes.entry(time_by_resolution(message, task)).or_insert(0) += change;
+    }
+    let mut members: i64 = 0;
+    group_messages_by_time(&membership_changes, task)
+        .into_iter()
+        .map(|(label, _)| {
+            members += net_changes.get(&label).copied().unwrap_or(0);
+            (label, members as f64)
+        })
+        .collect()
 }
 
-fn filter_and_count_messages(
+/// Sums up the counts of all time buckets up to (and including) each bucket.
+fn running_totals(counts: &[(String, usize)]) -> Vec<(String, f64)> {
+    counts
+        .iter()
+        .scan(0, |total, (label, count)| {
+            *total += count;
+            Some((label.clone(), *total as f64))
+        })
+        .collect()
+}
+
+/// Counts the distinct users who posted per ISO week, optionally only counting matching messages.
+fn count_weekly_active_users(
     messages: &[MessageInChannel],
+    task: &PlotTask,
     channel_pattern: &str,
-    message_pattern: &str,
-    resolution: &TimeResolution,
+    message_pattern: Option<&str>,
+) -> Vec<(String, usize)> {
+    let weekly_task = PlotTask {
+        resolution: plot::TimeResolution::Weekly,
+        ..task.clone()
+    };
+    let message_matcher = message_pattern.map(|pattern| matcher(task, pattern));
+    let messages_to_plot = find_messages(messages, task, channel_pattern, |x| {
+        message_matcher.as_ref().is_none_or(|pattern| x.message.contains(pattern))
+    });
+    count_users_by_time(&messages_to_plot, &weekly_task)
+}
+
+/// Counts the distinct users who posted the given messages per time bucket (ignoring messages without user),
+/// including empty buckets in between.
+fn count_users_by_time(
+    messages_to_plot: &[&MessageInChannel],
+    task: &PlotTask,
+) -> Vec<(String, usize)> {
+    let user_counts = group_by_time(messages_to_plot, task)
+        .into_iter()
+        .map(|(label, bucket)| {
+            let users: HashSet<&str> = bucket.iter().filter_map(|x| x.message.user()).collect();
+            (label, users.len())
+        })
+        .collect();
+    fill_empty_buckets(user_counts, &task.resolution)
+}
+
+/// Counts the reactions with the given emoji per time bucket of the messages they react to,
+/// including empty buckets in between.
+fn count_reactions(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    emoji: &str,
 ) -> Vec<(String, usize)> {
-    let messages_to_plot: Vec<&MessageInChannel> = messages
+    let messages_to_plot = find_messages(messages, task, channel_pattern, |x| {
+        x.message.reaction_count_of(emoji) > 0
+    });
+    println!("Found {} messages with '{}' reactions.", messages_to_plot.len(), emoji);
+    let reaction_counts = group_by_time(&messages_to_plot, task)
+        .into_iter()
+        .map(|(label, bucket)| {
+            let count: u64 = bucket.iter().map(|x| x.message.reaction_count_of(emoji)).sum();
+            (label, count as usize)
+        })
+        .collect();
+    fill_empty_buckets(reaction_counts, &task.resolution)
+}
+
+/// Calculates the Jaccard overlap between the users who posted and the users who reacted, per time bucket.
+///
+/// Reactions count in the bucket of the message they react to; buckets without any users have an overlap of zero.
+fn reactor_poster_overlaps(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+) -> Vec<(String, f64)> {
+    let messages_to_plot = find_messages(messages, task, channel_pattern, |_| true);
+    group_by_time(&messages_to_plot, task)
+        .into_iter()
+        .map(|(label, bucket)| {
+            let posters: HashSet<&str> = bucket.iter().filter_map(|x| x.message.user()).collect();
+            let reactors: HashSet<&str> = bucket.iter().flat_map(|x| x.message.reactors()).collect();
+            let union = posters.union(&reactors).count();
+            let overlap = if union == 0 {
+                0.0
+            } else {
+                posters.intersection(&reactors).count() as f64 / union as f64
+            };
+            (label, overlap)
+        })
+        .collect()
+}
+
+/// Counts the messages matching any pattern of each group, per time bucket.
+///
+/// All groups share the same time buckets (those with a message matching any group), with zero counts where needed.
+fn count_pattern_groups(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    pattern_groups: &[(String, Vec<String>)],
+) -> Vec<(String, Vec<(String, usize)>)> {
+    let group_matchers: Vec<Vec<slack::Matcher>> = pattern_groups
+        .iter()
+        .map(|(_, patterns)| patterns.iter().map(|p| matcher(task, p)).collect())
+        .collect();
+    let matches_group = |msg: &MessageInChannel, matchers: &[slack::Matcher]| {
+        matchers.iter().any(|m| msg.message.contains(m))
+    };
+    let matching_messages = find_messages(messages, task, channel_pattern, |x| {
+        group_matchers.iter().any(|matchers| matches_group(x, matchers))
+    });
+    let buckets = group_by_time(&matching_messages, task);
+    pattern_groups
+        .iter()
+        .zip(&group_matchers)
+        .map(|((group, _), matchers)| {
+            let counts = buckets
+                .iter()
+                .map(|(label, bucket)| {
+                    let count = bucket.iter().filter(|x| matches_group(x, matchers)).count();
+                    (label.clone(), count)
+                })
+                .collect();
+            (group.clone(), counts)
+        })
+        .collect()
+}
+
+/// Calculates the median time (in minutes) between a message and its first reaction, per time bucket.
+///
+/// This requires reaction timestamps, which standard Slack exports do not contain. Instead of
+/// approximating them, an error is returned if no reacted-to message has one.
+fn median_time_to_first_reaction(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+) -> Result<Vec<(String, f64)>, Box<dyn std::error::Error>> {
+    let reacted_messages = find_messages(messages, task, channel_pattern, |x| {
+        x.message.has_reactions()
+    });
+    let timed_messages: Vec<&MessageInChannel> = reacted_messages
+        .iter()
+        .copied()
+        .filter(|x| x.message.first_reaction_time().is_some())
+        .collect();
+    if timed_messages.is_empty() {
+        return Err(format!(
+            "none of the {} messages with reactions in channels matching '{}' has a reaction timestamp \
+             (standard Slack exports do not include them)",
+            reacted_messages.len(),
+            channel_pattern
+        )
+        .into());
+    }
+    Ok(group_by_time(&timed_messages, task)
+        .into_iter()
+        .map(|(label, bucket)| {
+            let minutes: Vec<f64> = bucket
+                .iter()
+                .filter_map(|x| {
+                    let delay = x.message.first_reaction_time()? - x.message.time()?;
+                    Some(delay.num_seconds() as f64 / 60.0)
+                })
+                .collect();
+            (label, stats::median(&minutes))
+        })
+        .collect())
+}
+
+/// Plots the averages of the values per time label (with error bars, if configured) and returns them.
+fn plot_averages(
+    task: &PlotTask,
+    description: &str,
+    values: Vec<(String, f64)>,
+) -> Result<Vec<(String, f64)>, Box<dyn std::error::Error>> {
+    if task.show_error_bars.unwrap_or(false) {
+        let spreads = spread_per_bucket(values);
+        plot::error_bar_plot(task, description, &spreads)?;
+        Ok(spreads
+            .into_iter()
+            .map(|(label, average, _)| (label, average))
+            .collect())
+    } else {
+        let averages = average_per_bucket(values);
+        plot::line_plot(task, description, &averages)?;
+        Ok(averages)
+    }
+}
+
+/// Calculates the average and the standard deviation of the values per time label, ordered by label.
+fn spread_per_bucket(values: Vec<(String, f64)>) -> Vec<(String, f64, f64)> {
+    let mut buckets: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for (label, value) in values {
+        buckets.entry(label).or_default().push(value);
+    }
+    buckets
+        .into_iter()
+        .map(|(label, bucket)| {
+            let (average, std_dev) = stats::mean_and_std_dev(&bucket);
+            (label, average, std_dev)
+        })
+        .collect()
+}
+
+/// Averages the values per time label, ordered by label.
+fn average_per_bucket(values: Vec<(String, f64)>) -> Vec<(String, f64)> {
+    let mut buckets: BTreeMap<String, (f64, usize)> = BTreeMap::new();
+    for (label, value) in values {
+        let (sum, count) = buckets.entry(label).or_insert((0.0, 0));
+        *sum += value;
+        *count += 1;
+    }
+    buckets
+        .into_iter()
+        .map(|(label, (sum, count))| (label, sum / count as f64))
+        .collect()
+}
+
+/// Splits the matching messages by a predicate and counts both parts per time bucket.
+///
+/// Both returned series share the labels of all matching messages (with zero counts where needed),
+/// so they can be passed to `plot::ratio_plot` without losing any buckets.
+#[allow(clippy::type_complexity)]
+fn filter_and_split_messages<P>(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    message_pattern: &str,
+    predicate: P,
+) -> (Vec<(String, usize)>, Vec<(String, usize)>)
+where
+    P: Fn(&MessageInChannel) -> bool,
+{
+    let matching_messages = find_matching_messages(messages, task, channel_pattern, message_pattern);
+    split_messages(&matching_messages, task, predicate)
+}
+
+/// Splits the messages by a predicate and counts both parts per time bucket, sharing the labels of all messages.
+#[allow(clippy::type_complexity)]
+fn split_messages<P>(
+    matching_messages: &Vec<&MessageInChannel>,
+    task: &PlotTask,
+    predicate: P,
+) -> (Vec<(String, usize)>, Vec<(String, usize)>)
+where
+    P: Fn(&MessageInChannel) -> bool,
+{
+    let selected_messages: Vec<&MessageInChannel> = matching_messages
+        .iter()
+        .copied()
+        .filter(|x| predicate(x))
+        .collect();
+    let selected_counts: HashMap<String, usize> =
+        group_messages_by_time(&selected_messages, task)
+            .into_iter()
+            .collect();
+    group_messages_by_time(matching_messages, task)
+        .into_iter()
+        .map(|(label, total)| {
+            let selected = selected_counts.get(&label).copied().unwrap_or(0);
+            ((label.clone(), selected), (label, total - selected))
+        })
+        .unzip()
+}
+
+/// Splits the questions, i.e. thread roots containing a question mark, by whether another user replied in their
+/// thread within `sla_hours`, and counts both parts per time bucket. Questions without any reply are not covered.
+#[allow(clippy::type_complexity)]
+fn split_questions_by_response(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+    sla_hours: f64,
+) -> (Vec<(String, usize)>, Vec<(String, usize)>) {
+    let channel_messages = find_messages(messages, task, channel_pattern, |_| true);
+    let replies = replies_by_thread(&channel_messages);
+    let questions: Vec<&MessageInChannel> = channel_messages
+        .iter()
+        .copied()
+        .filter(|x| x.message.thread_ts() == x.message.ts() && x.message.is_question())
+        .collect();
+    println!("Found {} questions in channels matching '{}'.", questions.len(), channel_pattern);
+    let sla = TimeDelta::milliseconds((sla_hours * 3_600_000.0) as i64);
+    split_messages(&questions, task, |question| {
+        let Some(deadline) = question.message.time_precise().map(|time| time + sla) else {
+            return false;
+        };
+        replies
+            .get(&(question.channel.as_str(), question.message.ts()))
+            .is_some_and(|thread_replies| {
+                thread_replies.iter().any(|reply| {
+                    reply.message.user() != question.message.user()
+                        && reply.message.time_precise().is_some_and(|time| time <= deadline)
+                })
+            })
+    })
+}
+
+/// Calculates the median and 90th percentile of the minutes until each thread root received its first reply
+/// from another user, per time bucket of the roots (which are left out if they received no such reply).
+fn first_response_minutes(
+    messages: &[MessageInChannel],
+    task: &PlotTask,
+    channel_pattern: &str,
+) -> Vec<(String, Vec<(String, f64)>)> {
+    let channel_messages = find_messages(messages, task, channel_pattern, |_| true);
+    let replies = replies_by_thread(&channel_messages);
+    let mut response_minutes: HashMap<(&str, &str), f64> = HashMap::new();
+    for root in channel_messages.iter().filter(|x| x.message.thread_ts() == x.message.ts()) {
+        let key = (root.channel.as_str(), root.message.ts());
+        let first_reply_time = replies.get(&key).and_then(|thread_replies| {
+            thread_replies
+                .iter()
+                .filter(|reply| reply.message.user() != root.message.user())
+                .filter_map(|reply| reply.message.time_precise())
+                .min()
+        });
+        if let (Some(reply_time), Some(root_time)) = (first_reply_time, root.message.time_precise()) {
+            response_minutes.insert(key, (reply_time - root_time).num_milliseconds() as f64 / 60_000.0);
+        }
+    }
+    let answered_roots: Vec<&MessageInChannel> = channel_messages
         .iter()
-        .filter(|x| x.channel.contains(channel_pattern) && x.message.contains(message_pattern))
+        .copied()
+        .filter(|x| response_minutes.contains_key(&(x.channel.as_str(), x.message.ts())))
         .collect();
-    println!("Found {} messages matching '{}'.", messages_to_plot.len(), message_pattern);
-    group_messages_by_time(&messages_to_plot, resolution)
+    let buckets: Vec<(String, Vec<f64>)> = group_by_time(&answered_roots, task)
+        .into_iter()
+        .map(|(label, bucket)| {
+            let minutes = bucket
+                .iter()
+                .map(|x| response_minutes[&(x.channel.as_str(), x.message.ts())])
+                .collect();
+            (label, minutes)
+        })
+        .collect();
+    [("median", 50.0), ("p90", 90.0)]
+        .into_iter()
+        .map(|(name, percentile)| {
+            let series = buckets
+                .iter()
+                .map(|(label, minutes)| (label.clone(), stats::percentile(minutes, percentile)))
+                .collect();
+            (name.to_string(), series)
+        })
+        .collect()
+}
+
+/// Collects the replies of each thread, by channel and thread timestamp.
+fn replies_by_thread<'a>(
+    channel_messages: &[&'a MessageInChannel],
+) -> HashMap<(&'a str, &'a str), Vec<&'a MessageInChannel>> {
+    let mut replies: HashMap<(&str, &str), Vec<&MessageInChannel>> = HashMap::new();
+    for message in channel_messages.iter().filter(|x| x.message.thread_ts() != x.message.ts()) {
+        replies
+            .entry((message.channel.as_str(), message.message.thread_ts()))
+            .or_default()
+            .push(message);
+    }
+    replies
+}
+
+/// Group (time-sorted) messages by the task's `TimeResolution`, keeping the order of the buckets.
+///
+/// Buckets of cyclic resolutions recur, so their messages are collected in a single bucket per label instead.
+fn group_by_time<'a>(
+    messages: &[&'a MessageInChannel],
+    task: &PlotTask,
+) -> Vec<(String, Vec<&'a MessageInChannel>)> {
+    let cyclic = task.resolution.cyclic_labels().is_some();
+    let mut buckets: Vec<(String, Vec<&MessageInChannel>)> = Vec::new();
+    for message in messages {
+        let time_label = time_by_resolution(message, task);
+        let existing_bucket = if cyclic {
+            buckets.iter_mut().find(|(label, _)| *label == time_label)
+        } else {
+            buckets.last_mut().filter(|(last_label, _)| *last_label == time_label)
+        };
+        match existing_bucket {
+            Some((_, bucket)) => bucket.push(message),
+            None => buckets.push((time_label, vec![message])),
+        }
+    }
+    if cyclic {
+        buckets.sort_by(|(label_a, _), (label_b, _)| label_a.cmp(label_b));
+    }
+    buckets
+}
+
+/// Inserts zero counts for all time buckets without messages between the first and the last bucket,
+/// so that periods without activity remain visible.
+///
+/// At cyclic resolutions, all buckets of the cycle are included, and counts for the same bucket are summed up.
+fn fill_empty_buckets(
+    message_counts: Vec<(String, usize)>,
+    resolution: &plot::TimeResolution,
+) -> Vec<(String, usize)> {
+    if let Some(labels) = resolution.cyclic_labels() {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for (label, count) in message_counts {
+            *counts.entry(label).or_insert(0) += count;
+        }
+        return labels
+            .into_iter()
+            .map(|label| {
+                let count = counts.get(&label).copied().unwrap_or(0);
+                (label, count)
+            })
+            .collect();
+    }
+    let mut filled_counts: Vec<(String, usize)> = Vec::with_capacity(message_counts.len());
+    for (label, count) in message_counts {
+        let mut next_label = filled_counts
+            .last()
+            .and_then(|(last_label, _)| resolution.next_label(last_label));
+        while let Some(empty_label) = next_label {
+            if empty_label >= label {
+                break;
+            }
+            next_label = resolution.next_label(&empty_label);
+            filled_counts.push((empty_label, 0));
+        }
+        filled_counts.push((label, count));
+    }
+    filled_counts
 }
 
-/// Group messages by `TimeResolution` and count them.
+/// Group messages by the task's `TimeResolution` and count them, including empty buckets in between.
 fn group_messages_by_time(
     messages_to_plot: &Vec<&MessageInChannel>,
-    resolution: &TimeResolution,
+    task: &PlotTask,
 ) -> Vec<(String, usize)> {
     let mut message_counts: Vec<(String, usize)> = Vec::new();
     let mut last_count: usize = 0;
     let mut last_label: String = "".to_string();
     for (index, message) in messages_to_plot.iter().enumerate() {
-        let time_label = time_by_resolution(message, resolution);
+        let time_label = time_by_resolution(message, task);
         if index == 0 {
             last_count = 1;
             last_label = time_label.clone();
@@ -100,15 +2265,1917 @@ fn group_messages_by_time(
             message_counts.push((last_label.clone(), last_count));
         }
     }
-    
-    message_counts
+    fill_empty_buckets(message_counts, &task.resolution)
 }
 
-/// Convert the message time to a string based on the `TimeResolution`.
-fn time_by_resolution(msg: &MessageInChannel, resolution: &TimeResolution) -> String {
-    return match resolution {
-        TimeResolution::Daily => msg.message.time().format("%Y-%m-%d").to_string(),
-        TimeResolution::Monthly => msg.message.time().format("%Y-%m").to_string(),
-        TimeResolution::Yearly => msg.message.time().format("%Y").to_string(),
+/// Convert the message time to a string based on the task's `TimeResolution`.
+///
+/// Messages with invalid timestamps are left out by `is_included`, so they should never get here.
+fn time_by_resolution(msg: &MessageInChannel, task: &PlotTask) -> String {
+    msg.message.time().map(|time| time_label(&time, task)).unwrap_or_default()
+}
+
+/// Convert a time to a string based on the task's `TimeResolution` and time zone, rounding it first if configured.
+fn time_label(time: &DateTime<Utc>, task: &PlotTask) -> String {
+    let time = match task.time_round_minutes {
+        Some(minutes) => round_time(time, minutes),
+        None => *time,
     };
-}
\ No newline at end of file
+    // Time zones are checked when validating the task
+    let time_zone = task.time_zone().unwrap_or(Tz::UTC);
+    task.resolution.label(&time.with_timezone(&time_zone))
+}
+
+/// Round a time to the nearest multiple of the given number of minutes.
+fn round_time(time: &DateTime<Utc>, minutes: u32) -> DateTime<Utc> {
+    time.duration_round(TimeDelta::minutes(minutes.max(1) as i64))
+        .unwrap_or(*time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plot::TimeResolution;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn process_task(
+        task: &PlotTask,
+        messages: &[MessageInChannel],
+    ) -> Result<Vec<(String, f64)>, Box<dyn std::error::Error>> {
+        process_task_counting_matches(task, messages).0
+    }
+
+    fn task(resolution: TimeResolution) -> PlotTask {
+        PlotTask::new(
+            plot::Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            resolution,
+            "test.png",
+        )
+    }
+
+    fn message_in_channel(file_path: &str, message: serde_json::Value) -> MessageInChannel {
+        MessageInChannel::new(
+            file_path,
+            serde_json::from_value(message).expect("Invalid test message"),
+        )
+    }
+
+    #[test]
+    fn test_edit_rate() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735725600.000100", "text": "deploy done"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({
+                    "type": "message",
+                    "ts": "1735729200.000200",
+                    "text": "deploy failed",
+                    "edited": {"user": "U01", "ts": "1735729260.000000"}
+                }),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735732800.000300", "text": "lunch?"}),
+            ),
+        ];
+        let (edited, unedited) = filter_and_split_messages(
+            &messages,
+            &task(TimeResolution::Daily),
+            "general",
+            "deploy",
+            |x| x.message.is_edited(),
+        );
+        assert_eq!(edited, vec![("2025-01-01".to_string(), 1)]);
+        assert_eq!(unedited, vec![("2025-01-01".to_string(), 1)]);
+        let labels = vec!["2025-01-01".to_string()];
+        assert_eq!(
+            plot::calculate_time_series_ratios(
+                &task(TimeResolution::Daily),
+                &labels,
+                &edited,
+                &unedited
+            ),
+            vec![("2025-01-01".to_string(), 0.5)]
+        );
+    }
+
+    #[test]
+    fn test_link_count() {
+        let messages = vec![
+            message_in_channel(
+                "dev",
+                json!({
+                    "type": "message",
+                    "ts": "1735725600.000100",
+                    "text": "Fixed in <https://github.com/roland-ewald/slackrs/pull/1>"
+                }),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "ts": "1735729200.000200", "text": "github is down again"}),
+            ),
+        ];
+        assert_eq!(
+            filter_and_count_linking_messages(
+                &messages,
+                &task(TimeResolution::Daily),
+                "dev",
+                "github"
+            ),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_deleted_messages() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735725600.000100", "text": "deploy done"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({
+                    "type": "message",
+                    "subtype": "tombstone",
+                    "ts": "1735729200.000200",
+                    "text": "This message was deleted."
+                }),
+            ),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "general", ""),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+        task.include_deleted = Some(true);
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "general", ""),
+            vec![("2025-01-01".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn test_average_thread_participants() {
+        let messages = vec![
+            message_in_channel(
+                "support",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "help?"}),
+            ),
+            message_in_channel(
+                "support",
+                json!({
+                    "type": "message",
+                    "user": "U02",
+                    "ts": "1735729200.000200",
+                    "thread_ts": "1735725600.000100",
+                    "text": "sure"
+                }),
+            ),
+            message_in_channel(
+                "support",
+                json!({"type": "message", "user": "U01", "ts": "1735729300.000300", "text": "thanks"}),
+            ),
+        ];
+        assert_eq!(
+            average_per_bucket(thread_participants(
+                &messages,
+                &task(TimeResolution::Daily),
+                "support"
+            )),
+            vec![("2025-01-01".to_string(), 1.5)]
+        );
+    }
+
+    #[test]
+    fn test_count_cumulative_users() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735812000.000200", "text": "hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735812100.000300", "text": "hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735898400.000400", "text": "hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U03", "ts": "1735898500.000500", "text": "hi"}),
+            ),
+        ];
+        assert_eq!(
+            count_cumulative_users(&messages, &task(TimeResolution::Daily), "general"),
+            vec![
+                ("2025-01-01".to_string(), 1),
+                ("2025-01-02".to_string(), 2),
+                ("2025-01-03".to_string(), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_count_weekly_active_users() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735812000.000200", "text": "hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735898400.000300", "text": "release"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1736244000.000400", "text": "release"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U03", "ts": "1736330400.000500", "text": "hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1736416800.000600", "text": "hi"}),
+            ),
+        ];
+        let task = task(TimeResolution::Daily);
+        assert_eq!(
+            count_weekly_active_users(&messages, &task, "general", None),
+            vec![("2025-W01".to_string(), 2), ("2025-W02".to_string(), 2)]
+        );
+        assert_eq!(
+            count_weekly_active_users(&messages, &task, "general", Some("release")),
+            vec![("2025-W01".to_string(), 1), ("2025-W02".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_count_pattern_groups() {
+        let messages = vec![
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "kubernetes and aws"}),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U02", "ts": "1735725700.000200", "text": "new dbt model"}),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U01", "ts": "1735812000.000300", "text": "terraform plan"}),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U03", "ts": "1735812100.000400", "text": "lunch?"}),
+            ),
+        ];
+        let pattern_groups = vec![
+            ("data".to_string(), vec!["dbt".to_string()]),
+            (
+                "infra".to_string(),
+                vec!["kubernetes".to_string(), "terraform".to_string(), "aws".to_string()],
+            ),
+        ];
+        assert_eq!(
+            count_pattern_groups(&messages, &task(TimeResolution::Daily), "dev", &pattern_groups),
+            vec![
+                (
+                    "data".to_string(),
+                    vec![("2025-01-01".to_string(), 1), ("2025-01-02".to_string(), 0)]
+                ),
+                (
+                    "infra".to_string(),
+                    vec![("2025-01-01".to_string(), 1), ("2025-01-02".to_string(), 1)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_time_to_first_reaction() {
+        let mut messages = vec![message_in_channel(
+            "general",
+            json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "release",
+                "reactions": [{"name": "tada", "users": ["U02"], "count": 1}]}),
+        )];
+        let error = median_time_to_first_reaction(&messages, &task(TimeResolution::Daily), "general")
+            .expect_err("Reaction times must not be made up");
+        assert!(error.to_string().contains("reaction timestamp"));
+
+        messages.push(message_in_channel(
+            "general",
+            json!({"type": "message", "user": "U01", "ts": "1735725600.000200", "text": "hotfix",
+                "reactions": [{"name": "eyes", "users": ["U02"], "count": 1, "ts": "1735725900.000000"}]}),
+        ));
+        assert_eq!(
+            median_time_to_first_reaction(&messages, &task(TimeResolution::Daily), "general")
+                .expect("Reaction times are available"),
+            vec![("2025-01-01".to_string(), 5.0)]
+        );
+    }
+
+    #[test]
+    fn test_process_tasks_with_threads() {
+        assert_eq!(run_with_threads(1, rayon::current_num_threads).unwrap(), 1);
+        assert_eq!(run_with_threads(1, rayon::current_thread_index).unwrap(), Some(0));
+
+        let output_file = std::env::temp_dir().join("slackrs-test-process-tasks-with-threads.png");
+        let mut single_task = task(TimeResolution::Daily);
+        single_task.output_file_name = output_file.to_str().unwrap().to_string();
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "@group hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735812000.000200", "text": "@group hi"}),
+            ),
+        ];
+        let results = process_tasks_with_threads(&[single_task.clone()], &messages, 1)
+            .expect("Processing failed");
+        assert_eq!(
+            results,
+            vec![TaskSeries {
+                task: single_task,
+                series: vec![("2025-01-01".to_string(), 1.0), ("2025-01-02".to_string(), 1.0)],
+            }]
+        );
+        std::fs::remove_file(&output_file).unwrap();
+        std::fs::remove_file(output_file.with_extension("png.csv")).unwrap();
+    }
+
+    #[test]
+    fn test_process_tasks_cached() {
+        let dir = std::env::temp_dir().join("slackrs-test-process-tasks-cached");
+        std::fs::create_dir_all(&dir).unwrap();
+        let cache_file = dir.join("cache.json");
+        let output_file = dir.join("mentions.png");
+        let _ = std::fs::remove_file(&cache_file);
+        let mut cached_task = task(TimeResolution::Daily);
+        cached_task.output_file_name = output_file.to_str().unwrap().to_string();
+        cached_task.emit_timestamps = Some(true);
+        let timestamps_file = dir.join("mentions.png-timestamps-.txt");
+        let mut messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735812000.000200", "text": "hi"}),
+            ),
+        ];
+        let tasks = vec![cached_task];
+        process_tasks_cached(&tasks, &messages, &cache_file, ErrorPolicy::Abort)
+            .expect("Processing failed");
+        assert_eq!(std::fs::read_to_string(&timestamps_file).unwrap().lines().count(), 2);
+
+        // Unchanged series must not be rendered again
+        std::fs::write(&output_file, "not re-rendered").unwrap();
+        let results =
+            process_tasks_cached(&tasks, &messages, &cache_file, ErrorPolicy::Abort)
+            .expect("Processing failed");
+        assert_eq!(results[0].series.len(), 2);
+        assert_eq!(std::fs::read_to_string(&output_file).unwrap(), "not re-rendered");
+
+        // Changed series must be rendered again
+        messages.push(message_in_channel(
+            "general",
+            json!({"type": "message", "user": "U03", "ts": "1735898400.000300", "text": "hi"}),
+        ));
+        process_tasks_cached(&tasks, &messages, &cache_file, ErrorPolicy::Abort)
+            .expect("Processing failed");
+        assert_ne!(std::fs::read(&output_file).unwrap(), b"not re-rendered");
+        assert_eq!(std::fs::read_to_string(&timestamps_file).unwrap().lines().count(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_process_tasks_cached_renders_like_uncached() {
+        let dir = std::env::temp_dir().join("slackrs-test-process-tasks-cached-ratio");
+        let _ = std::fs::remove_dir_all(&dir);
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "bug"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735729200.000200", "text": "feature"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735812000.000300", "text": "bug"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735815600.000400", "text": "bug"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U03", "ts": "1735819200.000500", "text": "feature"}),
+            ),
+        ];
+        let ratio_tasks = |name: &str| {
+            let output_dir = dir.join(name);
+            std::fs::create_dir_all(&output_dir).unwrap();
+            let mut ratio_task = task(TimeResolution::Daily);
+            ratio_task.metric = plot::Metric::StringMessageCountRatio {
+                channel_pattern: "general".to_string(),
+                message_pattern1: "bug".to_string(),
+                message_pattern2: "feature".to_string(),
+            };
+            ratio_task.output_file_name = output_dir.join("ratio.png").to_str().unwrap().to_string();
+            vec![ratio_task]
+        };
+
+        process_tasks_with_policy(&ratio_tasks("uncached"), &messages, ErrorPolicy::Abort)
+            .expect("Processing failed");
+        process_tasks_cached(&ratio_tasks("cached"), &messages, &dir.join("cache.json"), ErrorPolicy::Abort)
+            .expect("Processing failed");
+        let read_files = |name: &str| {
+            let mut files: Vec<(std::ffi::OsString, Vec<u8>)> = std::fs::read_dir(dir.join(name))
+                .unwrap()
+                .map(|entry| {
+                    let entry = entry.unwrap();
+                    (entry.file_name(), std::fs::read(entry.path()).unwrap())
+                })
+                .collect();
+            files.sort();
+            files
+        };
+        let uncached_files = read_files("uncached");
+        assert!(uncached_files.len() > 1);
+        assert_eq!(read_files("cached"), uncached_files);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_match_count_of_ratio_task() {
+        let mut ratio_task = task(TimeResolution::Daily);
+        ratio_task.metric = plot::Metric::StringMessageCountRatio {
+            channel_pattern: "general".to_string(),
+            message_pattern1: "bug".to_string(),
+            message_pattern2: "feature".to_string(),
+        };
+        ratio_task.skip_output = true;
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "a bug"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735729200.000200", "text": "a feature"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U03", "ts": "1735732800.000300", "text": "hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "subtype": "channel_join", "user": "U04", "ts": "1735736400.000400",
+                    "text": "joined to report a bug"}),
+            ),
+        ];
+        let outcomes = process_tasks_with_outcomes(&[ratio_task], &messages, ErrorPolicy::Abort)
+            .expect("Processing failed");
+        // Neither the unmatched message nor the (uncountable) join are matches, unlike in a scan of the channel
+        assert_eq!(outcomes[0].match_count, 2);
+    }
+
+    #[test]
+    fn test_error_policies() {
+        let dir = std::env::temp_dir().join("slackrs-test-error-policies");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut working_task = task(TimeResolution::Daily);
+        working_task.output_file_name = dir.join("working.png").to_str().unwrap().to_string();
+        let mut failing_task = task(TimeResolution::Daily);
+        failing_task.output_file_name =
+            dir.join("missing-dir").join("failing.png").to_str().unwrap().to_string();
+        let tasks = vec![failing_task, working_task.clone()];
+        let messages = vec![message_in_channel(
+            "general",
+            json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hi"}),
+        )];
+
+        let error = process_tasks_with_policy(&tasks, &messages, ErrorPolicy::Abort)
+            .expect_err("Aborting must return the error");
+        assert!(error.to_string().contains("failing.png"));
+        for policy in [ErrorPolicy::Continue, ErrorPolicy::Retry] {
+            let results = process_tasks_with_policy(&tasks, &messages, policy)
+                .expect("Failing tasks must be skipped");
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].task, working_task);
+        }
+        assert_eq!("retry".parse::<ErrorPolicy>(), Ok(ErrorPolicy::Retry));
+        assert!("ignore".parse::<ErrorPolicy>().is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_round_time() {
+        let time = Utc.with_ymd_and_hms(2025, 1, 1, 12, 37, 0).unwrap();
+        assert_eq!(
+            round_time(&time, 15),
+            Utc.with_ymd_and_hms(2025, 1, 1, 12, 30, 0).unwrap()
+        );
+        let mut task = task(TimeResolution::Daily);
+        let late_time = Utc.with_ymd_and_hms(2025, 1, 1, 23, 55, 0).unwrap();
+        assert_eq!(time_label(&late_time, &task), "2025-01-01");
+        task.time_round_minutes = Some(15);
+        assert_eq!(time_label(&late_time, &task), "2025-01-02");
+    }
+
+    #[test]
+    fn test_average_max_reply_depth() {
+        let root_ts = "1735725600.000100";
+        let messages = vec![
+            message_in_channel(
+                "support",
+                json!({"type": "message", "ts": root_ts, "thread_ts": root_ts, "text": "help?"}),
+            ),
+            message_in_channel(
+                "support",
+                json!({"type": "message", "ts": "1735729200.000200", "thread_ts": root_ts, "text": "sure"}),
+            ),
+            message_in_channel(
+                "support",
+                json!({"type": "message", "ts": "1735729300.000300", "text": "unrelated"}),
+            ),
+        ];
+        let task = task(TimeResolution::Daily);
+        assert_eq!(
+            average_per_bucket(max_reply_depths(&messages, &task, "support")),
+            vec![("2025-01-01".to_string(), 0.5)]
+        );
+
+        let mut nested_messages = messages;
+        nested_messages.push(message_in_channel(
+            "support",
+            json!({"type": "message", "ts": "1735729400.000400", "thread_ts": "1735729200.000200", "text": "nested"}),
+        ));
+        assert_eq!(
+            average_per_bucket(max_reply_depths(&nested_messages, &task, "support")),
+            vec![("2025-01-01".to_string(), 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_participation_gini() {
+        let messages: Vec<MessageInChannel> = ["U01", "U02", "U02", "U02"]
+            .iter()
+            .map(|user| {
+                message_in_channel(
+                    "general",
+                    json!({"type": "message", "user": user, "ts": "1735725600.000100", "text": "hi"}),
+                )
+            })
+            .collect();
+        let message_refs: Vec<&MessageInChannel> = messages.iter().collect();
+        assert_eq!(participation_gini(&message_refs), 0.25);
+        assert_eq!(participation_gini(&message_refs[..1]), 0.0);
+    }
+
+    #[test]
+    fn test_min_message_length() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735725600.000100", "text": "ok"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735729200.000200", "text": "The deployment is done."}),
+            ),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.min_message_length = Some(5);
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "general", ""),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_emoji_only_message_count() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735725600.000100", "text": ":tada: 🎉"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735729200.000200", "text": "Released :tada:"}),
+            ),
+        ];
+        let task = task(TimeResolution::Daily);
+        let emoji_only = find_messages(&messages, &task, "general", |x| x.message.is_emoji_only());
+        assert_eq!(
+            group_messages_by_time(&emoji_only, &task),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_count_cross_posts() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735725600.000100", "text": "Release 1.0 is out!"}),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "ts": "1735726200.000200", "text": "release 1.0  is out!"}),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "ts": "1735726300.000300", "text": "Release notes follow"}),
+            ),
+            message_in_channel(
+                "random",
+                json!({"type": "message", "ts": "1735812000.000400", "text": "Release 1.0 is out!"}),
+            ),
+        ];
+        assert_eq!(
+            count_cross_posts(&messages, &task(TimeResolution::Daily), "elease", 60),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_count_duplicate_messages() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "Anyone there?"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735725660.000200", "text": "Anyone there?"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725900.000300", "text": "Anyone there?"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735812000.000400", "text": "Anyone there?"}),
+            ),
+        ];
+        assert_eq!(
+            count_duplicate_messages(&messages, &task(TimeResolution::Daily), "general", 10),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_count_co_occurrences() {
+        let messages = vec![
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "kubernetes on aws"}),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U02", "ts": "1735725700.000200", "text": "kubernetes locally"}),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U03", "ts": "1735725800.000300", "text": "aws bill"}),
+            ),
+        ];
+        assert_eq!(
+            count_co_occurrences(&messages, &task(TimeResolution::Daily), "dev", "kubernetes", "aws"),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_longest_silences() {
+        let messages = vec![
+            message_in_channel(
+                "abandoned",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hi"}),
+            ),
+            message_in_channel(
+                "abandoned",
+                json!({"type": "message", "user": "U01", "ts": "1735812000.000200", "text": "hi"}),
+            ),
+            message_in_channel(
+                "abandoned",
+                json!({"type": "message", "user": "U02", "ts": "1736676000.000300", "text": "anyone?"}),
+            ),
+            message_in_channel(
+                "active",
+                json!({"type": "message", "user": "U03", "ts": "1735725600.000400", "text": "hi"}),
+            ),
+        ];
+        assert_eq!(
+            longest_silences(&messages, &task(TimeResolution::Daily), ""),
+            vec![("abandoned".to_string(), 10.0), ("active".to_string(), 0.0)]
+        );
+    }
+
+    #[test]
+    fn test_term_adoption() {
+        let messages = vec![
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "the CI is red"}),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U02", "ts": "1735812000.000200", "text": "CI or pipeline?"}),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U01", "ts": "1735898400.000300", "text": "the pipeline is red"}),
+            ),
+        ];
+        let terms = vec![
+            ("CI".to_string(), vec!["CI".to_string()]),
+            ("pipeline".to_string(), vec!["pipeline".to_string()]),
+        ];
+        assert_eq!(
+            count_pattern_groups(&messages, &task(TimeResolution::Daily), "dev", &terms),
+            vec![
+                (
+                    "CI".to_string(),
+                    vec![
+                        ("2025-01-01".to_string(), 1),
+                        ("2025-01-02".to_string(), 1),
+                        ("2025-01-03".to_string(), 0)
+                    ]
+                ),
+                (
+                    "pipeline".to_string(),
+                    vec![
+                        ("2025-01-01".to_string(), 0),
+                        ("2025-01-02".to_string(), 1),
+                        ("2025-01-03".to_string(), 1)
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_emit_timestamps() {
+        let dir = std::env::temp_dir().join("slackrs-test-emit-timestamps");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut timestamp_task = task(TimeResolution::Daily);
+        timestamp_task.output_file_name = dir.join("mentions.png").to_str().unwrap().to_string();
+        timestamp_task.emit_timestamps = Some(true);
+        timestamp_task.metric = plot::Metric::MentionCount {
+            channel_pattern: "general".to_string(),
+            message_pattern: "@group".to_string(),
+        };
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "@group hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735725700.000200", "text": "hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735812000.123456", "text": "@group bye"}),
+            ),
+        ];
+        write_matching_timestamps(&timestamp_task, &messages).expect("Writing timestamps failed");
+
+        let timestamps =
+            std::fs::read_to_string(dir.join("mentions.png-timestamps-_group.txt")).unwrap();
+        assert_eq!(timestamps.lines().count(), 2);
+        assert_eq!(timestamps.lines().last(), Some("2025-01-02T10:00:00.123456Z"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_top_reacted_messages() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "release",
+                    "reactions": [{"name": "tada", "users": ["U02", "U03"], "count": 2}]}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735725700.000200", "text": "new office",
+                    "reactions": [{"name": "tada", "users": ["U01", "U03"], "count": 2},
+                                  {"name": "heart", "users": ["U04"], "count": 1}]}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U03", "ts": "1735725800.000300", "text": "lunch?"}),
+            ),
+        ];
+        let top_messages =
+            top_reacted_messages(&messages, &task(TimeResolution::Daily), "general", 1);
+        assert_eq!(top_messages.len(), 1);
+        assert_eq!(top_messages[0].message.text, "new office");
+        assert_eq!(snippet("a  long\ntext", 5), "a lo…");
+    }
+
+    #[test]
+    fn test_average_onboarding_activity() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hello"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735898400.000200", "text": "question"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1736071200.000300", "text": "hi all"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1736244000.000400", "text": "thanks"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1736676000.000500", "text": "later"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U03", "ts": "1738404000.000600", "text": "new here"}),
+            ),
+        ];
+        assert_eq!(
+            average_onboarding_activity(&messages, &task(TimeResolution::Monthly), "general", 7),
+            vec![("2025-01".to_string(), 2.0), ("2025-02".to_string(), 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_spread_per_bucket() {
+        let values = vec![
+            ("2025-01".to_string(), 1.0),
+            ("2025-02".to_string(), 4.0),
+            ("2025-01".to_string(), 3.0),
+        ];
+        assert_eq!(
+            spread_per_bucket(values),
+            vec![
+                ("2025-01".to_string(), 2.0, 1.0),
+                ("2025-02".to_string(), 4.0, 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_count_message_types() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hi"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735725700.000200", "text": "hello"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "subtype": "channel_join", "user": "U03", "ts": "1735725800.000300",
+                    "text": "<@U03> has joined the channel"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "subtype": "bot_message", "bot_id": "B01", "ts": "1735725900.000400",
+                    "text": "Build passed"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735726000.000500", "text": "see file",
+                    "files": [{"id": "F01", "name": "report.pdf"}]}),
+            ),
+        ];
+        let category_counts = count_message_types(&messages, &task(TimeResolution::Daily), "general");
+        assert_eq!(
+            proportions(&category_counts),
+            vec![
+                ("regular".to_string(), 0.4),
+                ("joins".to_string(), 0.2),
+                ("bots".to_string(), 0.2),
+                ("file shares".to_string(), 0.2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exclamation_ratio() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735725600.000100", "text": "We shipped it!!"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735729200.000200", "text": "Shipped it."}),
+            ),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::ExclamationRatio {
+            channel_pattern: "general".to_string(),
+            message_pattern: "".to_string(),
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("2025-01-01".to_string(), 0.5)]
+        );
+    }
+
+    #[test]
+    fn test_all_caps_ratio() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735725600.000100", "text": "THE BUILD IS BROKEN AGAIN!!!"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735729200.000200", "text": "The build is green again :tada:"}),
+            ),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::AllCapsRatio {
+            channel_pattern: "general".to_string(),
+            message_pattern: "".to_string(),
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("2025-01-01".to_string(), 0.5)]
+        );
+    }
+
+    #[test]
+    fn test_quiet_hours_count() {
+        let messages = vec![
+            // 23:00 in Berlin (UTC+1 in winter)
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735768800.000100", "text": "still working"}),
+            ),
+            // 11:00 in Berlin
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735725600.000200", "text": "good morning"}),
+            ),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::QuietHoursCount {
+            channel_pattern: "general".to_string(),
+            start_hour: 20,
+            end_hour: 8,
+            timezone: Some("Europe/Berlin".to_string()),
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("2025-01-01".to_string(), 1.0)]
+        );
+        let summer_messages = vec![
+            // 08:30 in Berlin (UTC+2 in summer), so not quiet
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1751351400.000300", "text": "good morning"}),
+            ),
+            // 21:30 in Berlin
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1751398200.000400", "text": "still working"}),
+            ),
+        ];
+        // Without its own time zone, the metric uses the task's one
+        task.metric = plot::Metric::QuietHoursCount {
+            channel_pattern: "general".to_string(),
+            start_hour: 20,
+            end_hour: 8,
+            timezone: None,
+        };
+        task.timezone = Some("Europe/Berlin".to_string());
+        assert_eq!(
+            process_task(&task, &summer_messages).expect("Processing failed"),
+            vec![("2025-07-01".to_string(), 1.0)]
+        );
+        assert!(is_quiet_hour(7, 20, 8));
+        assert!(!is_quiet_hour(8, 20, 8));
+        assert!(is_quiet_hour(12, 12, 14));
+        assert!(!is_quiet_hour(14, 12, 14));
+    }
+
+    #[test]
+    fn test_per_member() {
+        let messages = vec![
+            message_in_channel(
+                "export/general/2025-01-01.json",
+                json!({"type": "message", "ts": "1735725600.000100", "text": "hello"}),
+            ),
+            message_in_channel(
+                "export/general/2025-01-01.json",
+                json!({"type": "message", "ts": "1735729200.000200", "text": "hi"}),
+            ),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::MentionCount {
+            channel_pattern: "general".to_string(),
+            message_pattern: "".to_string(),
+        };
+        task.per_member = Some(true);
+        task.skip_output = true;
+        assert!(process_task(&task, &messages).is_err());
+
+        let task = task.with_channel_members(&BTreeMap::from([("general".to_string(), 4)]));
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("2025-01-01".to_string(), 0.5)]
+        );
+    }
+
+    #[test]
+    fn test_group_messages_by_time_fills_empty_buckets() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735725600.000100", "text": "happy new year"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1743465600.000200", "text": "back again"}),
+            ),
+        ];
+        let task = task(TimeResolution::Monthly);
+        let messages_to_plot = find_messages(&messages, &task, "general", |_| true);
+        assert_eq!(
+            group_messages_by_time(&messages_to_plot, &task),
+            vec![
+                ("2025-01".to_string(), 1),
+                ("2025-02".to_string(), 0),
+                ("2025-03".to_string(), 0),
+                ("2025-04".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_process_tasks_diff() {
+        let message = |ts: &str| {
+            message_in_channel("general", json!({"type": "message", "ts": ts, "text": "hi"}))
+        };
+        // Export A has two messages on 2025-01-01, export B has one on 2025-01-01 and one on 2025-01-02
+        let messages_a = vec![message("1735725600.000100"), message("1735729200.000200")];
+        let messages_b = vec![message("1735725600.000100"), message("1735812000.000300")];
+        let mut task = task(TimeResolution::Daily);
+        task.skip_output = true;
+        let results = process_tasks_diff(&[task.clone()], &messages_a, &messages_b, ErrorPolicy::Abort)
+            .expect("Processing failed");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].task, task);
+        assert_eq!(
+            results[0].series,
+            vec![("2025-01-01".to_string(), -1.0), ("2025-01-02".to_string(), 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_unique_user_count() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "rust?"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735729200.000200", "text": "rust!"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735732800.000300", "text": "rust."}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "ts": "1735736400.000400", "text": "rust bot"}),
+            ),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::UniqueUserCount {
+            channel_pattern: "general".to_string(),
+            message_pattern: "rust".to_string(),
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("2025-01-01".to_string(), 2.0)]
+        );
+    }
+
+    #[test]
+    fn test_match_modes() {
+        let messages = vec![
+            message_in_channel(
+                "export/Dev/2025-01-01.json",
+                json!({"type": "message", "ts": "1735725600.000100", "text": "Found a Bug"}),
+            ),
+            message_in_channel(
+                "export/Dev/2025-01-01.json",
+                json!({"type": "message", "ts": "1735729200.000200", "text": "new issue"}),
+            ),
+            message_in_channel(
+                "export/Dev/2025-01-01.json",
+                json!({"type": "message", "ts": "1735732800.000300", "text": "lunch?"}),
+            ),
+        ];
+        let count = |match_mode, channel_pattern, message_pattern| {
+            let mut task = task(TimeResolution::Daily);
+            task.match_mode = match_mode;
+            filter_and_count_messages(&messages, &task, channel_pattern, message_pattern)
+        };
+        let day = |count| vec![("2025-01-01".to_string(), count)];
+        assert_eq!(count(None, "dev", "bug"), vec![]);
+        assert_eq!(count(Some(plot::MatchMode::Substring), "Dev", "Bug"), day(1));
+        assert_eq!(count(Some(plot::MatchMode::CaseInsensitiveSubstring), "dev", "bug"), day(1));
+        assert_eq!(count(Some(plot::MatchMode::Regex), "^Dev$", "(?i)bug|issue|ticket"), day(2));
+    }
+
+    #[test]
+    fn test_reactor_poster_overlaps() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({
+                    "type": "message",
+                    "user": "U01",
+                    "ts": "1735725600.000100",
+                    "text": "release!",
+                    "reactions": [
+                        {"name": "tada", "users": ["U02", "U03"], "count": 2},
+                        {"name": "+1", "users": ["U02"], "count": 1}
+                    ]
+                }),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735729200.000200", "text": "nice"}),
+            ),
+        ];
+        // Posters {U01, U02} and reactors {U02, U03} share one of three users
+        let overlaps = reactor_poster_overlaps(&messages, &task(TimeResolution::Daily), "general");
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].0, "2025-01-01");
+        assert!((overlaps[0].1 - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_process_tasks_outcomes() {
+        let dir = std::env::temp_dir().join("slackrs-test-process-tasks-outcomes");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut working_task = task(TimeResolution::Daily);
+        working_task.output_file_name = dir.join("working.png").to_str().unwrap().to_string();
+        let mut failing_task = task(TimeResolution::Daily);
+        failing_task.output_file_name =
+            dir.join("missing-dir").join("failing.png").to_str().unwrap().to_string();
+        let messages = vec![message_in_channel(
+            "general",
+            json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hi"}),
+        )];
+
+        let outcomes = process_tasks(&[failing_task.clone(), working_task.clone()], &messages)
+            .expect("Processing failed");
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[0].task, failing_task);
+        assert!(!outcomes[0].is_success());
+        assert_eq!(outcomes[1].task, working_task);
+        assert_eq!(outcomes[1].result, Ok(vec![("2025-01-01".to_string(), 1.0)]));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_count_messages_progressively() {
+        // As read from an archive, i.e. ordered by channel and not by time
+        let messages = || {
+            [
+                ("general/2025-01-03.json", "1735900000.000100", "deploy"),
+                ("general/2025-01-03.json", "1735900100.000100", "lunch"),
+                ("random/2025-01-01.json", "1735725600.000100", "deploy"),
+                ("random/2025-01-03.json", "1735900200.000100", "deploy again"),
+                ("dev/2025-01-01.json", "1735725700.000100", "deploy"),
+            ]
+            .into_iter()
+            .map(|(file_path, ts, text)| {
+                message_in_channel(file_path, json!({"type": "message", "ts": ts, "text": text}))
+            })
+        };
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::MentionCount {
+            channel_pattern: "".to_string(),
+            message_pattern: "deploy".to_string(),
+        };
+
+        let mut snapshots: Vec<BTreeMap<String, usize>> = Vec::new();
+        let counts = count_messages_progressively(&task, messages(), 2, |counts| snapshots.push(counts.clone()))
+            .expect("Counting failed");
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(snapshots[0], BTreeMap::from([("2025-01-03".to_string(), 1)]));
+        assert_eq!(
+            snapshots[1],
+            BTreeMap::from([("2025-01-01".to_string(), 1), ("2025-01-03".to_string(), 2)])
+        );
+
+        let mut sorted_messages: Vec<MessageInChannel> = messages().collect();
+        sorted_messages.sort_by_key(|x| x.message.time());
+        let batch_counts = filter_and_count_messages(&sorted_messages, &task, "", "deploy");
+        assert_eq!(counts, batch_counts);
+        assert_eq!(
+            snapshots[2],
+            batch_counts.into_iter().filter(|(_, count)| *count > 0).collect()
+        );
+
+        task.metric = plot::Metric::LongestSilence {
+            channel_pattern: "".to_string(),
+        };
+        assert!(count_messages_progressively(&task, messages(), 2, |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_process_tasks_without_images() {
+        let dir = std::env::temp_dir().join("slackrs-test-process-tasks-without-images");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut task = task(TimeResolution::Daily);
+        task.output_file_name = dir.join("counts.png").to_str().unwrap().to_string();
+        let task = task.without_image();
+        let messages = vec![message_in_channel(
+            "general",
+            json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hi"}),
+        )];
+
+        let outcomes = process_tasks(std::slice::from_ref(&task), &messages).expect("Processing failed");
+        assert_eq!(outcomes[0].result, Ok(vec![("2025-01-01".to_string(), 1.0)]));
+        assert!(dir.join("counts.png.csv").is_file());
+        assert!(!dir.join("counts.png").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_response_coverage() {
+        let question_ts = "1735725600.000100";
+        let messages = vec![
+            message_in_channel(
+                "support",
+                json!({"type": "message", "ts": question_ts, "user": "U1", "text": "How do I deploy?"}),
+            ),
+            message_in_channel(
+                "support",
+                json!({"type": "message", "ts": "1735729200.000200", "thread_ts": question_ts, "user": "U2", "text": "Via CI."}),
+            ),
+            message_in_channel(
+                "support",
+                json!({"type": "message", "ts": "1735732800.000300", "user": "U3", "text": "Is the build broken?"}),
+            ),
+            message_in_channel(
+                "support",
+                json!({"type": "message", "ts": "1735736400.000400", "user": "U3", "text": "Thanks in advance."}),
+            ),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::ResponseCoverage {
+            channel_pattern: "support".to_string(),
+            sla_hours: 2.0,
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("2025-01-01".to_string(), 0.5)]
+        );
+    }
+
+    #[test]
+    fn test_multi_mention_count() {
+        let messages = vec![
+            message_in_channel("dev", json!({"type": "message", "ts": "1735725600.000100", "text": "rust or go?"})),
+            message_in_channel("dev", json!({"type": "message", "ts": "1735812000.000200", "text": "rust!"})),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::MultiMentionCount {
+            channel_pattern: "dev".to_string(),
+            message_patterns: vec!["rust".to_string(), "go".to_string()],
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![
+                ("rust/2025-01-01".to_string(), 1.0),
+                ("rust/2025-01-02".to_string(), 1.0),
+                ("go/2025-01-01".to_string(), 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_excluded_subtypes() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "deploy done"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "subtype": "channel_join", "user": "U02", "ts": "1735725700.000200",
+                    "text": "<@U02> has joined the channel to deploy"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "subtype": "bot_message", "bot_id": "B01", "ts": "1735725800.000300",
+                    "text": "deploy started"}),
+            ),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "general", "deploy"),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+        task.excluded_subtypes = Some(vec!["channel_join".to_string()]);
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "general", "deploy"),
+            vec![("2025-01-01".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn test_time_zone() {
+        // 2025-01-02 01:30 UTC, i.e. the evening of 2025-01-01 in Los Angeles
+        let messages = vec![message_in_channel(
+            "general",
+            json!({"type": "message", "user": "U01", "ts": "1735781400.000100", "text": "still here"}),
+        )];
+        let mut task = task(TimeResolution::Daily);
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "", ""),
+            vec![("2025-01-02".to_string(), 1)]
+        );
+        task.timezone = Some("America/Los_Angeles".to_string());
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "", ""),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+        task.resolution = TimeResolution::Hourly;
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "", ""),
+            vec![("2025-01-01 17:00".to_string(), 1)]
+        );
+
+        task.timezone = Some("Pacific/Nowhere".to_string());
+        let error = process_task(&task, &messages).expect_err("Unknown time zones must be rejected");
+        assert!(error.to_string().contains("Unknown time zone 'Pacific/Nowhere'"));
+    }
+
+    #[test]
+    fn test_mention_count_by_user() {
+        let messages = vec![
+            message_in_channel("dev", json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "deploy"})),
+            message_in_channel("dev", json!({"type": "message", "user": "U02", "ts": "1735725700.000200", "text": "deploy"})),
+            message_in_channel("dev", json!({"type": "message", "user": "U01", "ts": "1735812000.000300", "text": "deploy"})),
+            message_in_channel("dev", json!({"type": "message", "user": "U03", "ts": "1735898400.000400", "text": "deploy"})),
+        ];
+        let task = task(TimeResolution::Daily);
+        assert_eq!(
+            filter_and_count_messages_by_user(&messages, &task, "dev", "deploy", 1),
+            vec![
+                (
+                    "U01".to_string(),
+                    vec![
+                        ("2025-01-01".to_string(), 1),
+                        ("2025-01-02".to_string(), 1),
+                        ("2025-01-03".to_string(), 0),
+                    ]
+                ),
+                (
+                    "other".to_string(),
+                    vec![
+                        ("2025-01-01".to_string(), 1),
+                        ("2025-01-02".to_string(), 0),
+                        ("2025-01-03".to_string(), 1),
+                    ]
+                ),
+            ]
+        );
+        assert_eq!(filter_and_count_messages_by_user(&messages, &task, "dev", "deploy", 3).len(), 3);
+    }
+
+    #[test]
+    fn test_skip_invalid_timestamps() {
+        let messages = vec![
+            message_in_channel("general", json!({"type": "message", "ts": "1735725600.000100", "text": "deploy"})),
+            message_in_channel("general", json!({"type": "message", "ts": "not a time", "text": "deploy"})),
+        ];
+        let task = task(TimeResolution::Daily);
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "general", "deploy"),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_hour_of_day_resolution() {
+        let messages = vec![
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hi"})),
+            message_in_channel("general", json!({"type": "message", "user": "U02", "ts": "1735812000.000200", "text": "hi"})),
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735815600.000300", "text": "hi"})),
+        ];
+        let task = task(TimeResolution::HourOfDay);
+        let counts = filter_and_count_messages(&messages, &task, "general", "hi");
+        assert_eq!(counts.len(), 24);
+        assert_eq!(counts[10], ("10".to_string(), 2));
+        assert_eq!(counts[11], ("11".to_string(), 1));
+        assert_eq!(counts.iter().map(|(_, count)| count).sum::<usize>(), 3);
+        let messages_to_plot: Vec<&MessageInChannel> = messages.iter().collect();
+        let user_counts = count_users_by_time(&messages_to_plot, &task);
+        assert_eq!(user_counts[10], ("10".to_string(), 2));
+        assert_eq!(user_counts[11], ("11".to_string(), 1));
+    }
+
+    #[test]
+    fn test_reaction_count() {
+        let messages = vec![
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "shipped",
+                    "reactions": [{"name": "tada", "users": ["U02", "U03"], "count": 2}]}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "ts": "1735729200.000200", "text": "done",
+                    "reactions": [{"name": "tada", "users": ["U01"], "count": 1}, {"name": "eyes", "users": ["U03"], "count": 1}]}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U03", "ts": "1735898400.000300", "text": "released",
+                    "reactions": [{"name": "tada", "users": ["U01"], "count": 1}]}),
+            ),
+        ];
+        assert_eq!(
+            count_reactions(&messages, &task(TimeResolution::Daily), "general", "tada"),
+            vec![
+                ("2025-01-01".to_string(), 3),
+                ("2025-01-02".to_string(), 0),
+                ("2025-01-03".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_thread_replies() {
+        let messages = vec![
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "thread_ts": "1735725600.000100",
+                    "text": "deploy failed"}),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U02", "ts": "1735725700.000200", "thread_ts": "1735725600.000100",
+                    "text": "retry the deploy"}),
+            ),
+            message_in_channel(
+                "dev",
+                json!({"type": "message", "user": "U01", "ts": "1735812000.000300", "thread_ts": "1735725600.000100",
+                    "text": "works now"}),
+            ),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::ThreadReplyCount {
+            channel_pattern: "dev".to_string(),
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("2025-01-01".to_string(), 1.0), ("2025-01-02".to_string(), 1.0)]
+        );
+        task.include_thread_replies = Some(false);
+        assert!(task.validate().is_err());
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "dev", "deploy"),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_channel_filters() {
+        let messages = vec![
+            message_in_channel("general", json!({"type": "message", "ts": "1735725600.000100", "text": "deploy"})),
+            message_in_channel("old-infra", json!({"type": "message", "ts": "1735725700.000200", "text": "deploy"})),
+            message_in_channel("infra", json!({"type": "message", "ts": "1735725800.000300", "text": "deploy"})),
+        ];
+        let channels: Vec<slack::Channel> = serde_json::from_value(json!([
+            {"name": "general", "created": 1500000000},
+            {"name": "old-infra", "created": 1600000000, "is_archived": true},
+            {"name": "infra", "created": 1700000000}
+        ]))
+        .unwrap();
+        let mut task = task(TimeResolution::Daily);
+        let count = |task: &PlotTask| filter_and_count_messages(&messages, &task.with_channels(&channels), "", "deploy");
+        assert_eq!(count(&task), vec![("2025-01-01".to_string(), 3)]);
+        task.exclude_archived = Some(true);
+        assert_eq!(count(&task), vec![("2025-01-01".to_string(), 2)]);
+        task.created_after = Some("2020-01-01".to_string());
+        assert_eq!(count(&task), vec![("2025-01-01".to_string(), 1)]);
+        task.created_after = Some("01/01/2020".to_string());
+        assert!(task.validate().is_err());
+    }
+
+    #[test]
+    fn test_stack_by_channel() {
+        let messages = vec![
+            message_in_channel("dev", json!({"type": "message", "ts": "1735725600.000100", "text": "deploy"})),
+            message_in_channel("ops", json!({"type": "message", "ts": "1735725700.000200", "text": "deploy"})),
+            message_in_channel("ops", json!({"type": "message", "ts": "1735812000.000300", "text": "deploy"})),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.stack_by_channel = Some(true);
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![
+                ("ops/2025-01-01".to_string(), 1.0),
+                ("ops/2025-01-02".to_string(), 1.0),
+                ("dev/2025-01-01".to_string(), 1.0),
+                ("dev/2025-01-02".to_string(), 0.0),
+            ]
+        );
+        task.per_member = Some(true);
+        assert!(task.validate().is_err());
+    }
+
+    #[test]
+    fn test_activity_heatmap() {
+        let messages = vec![
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hi"})),
+            message_in_channel("general", json!({"type": "message", "user": "U02", "ts": "1735812000.000200", "text": "hi"})),
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735815600.000300", "text": "hi"})),
+            message_in_channel("random", json!({"type": "message", "user": "U01", "ts": "1735815600.000400", "text": "hi"})),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::ActivityHeatmap {
+            channel_pattern: "general".to_string(),
+        };
+        task.skip_output = true;
+        let series = process_task(&task, &messages).expect("Processing failed");
+        assert_eq!(series.len(), 7 * 24);
+        assert_eq!(series[0], ("Mon/00".to_string(), 0.0));
+        assert_eq!(series[2 * 24 + 10], ("Wed/10".to_string(), 1.0));
+        assert_eq!(series[3 * 24 + 10], ("Thu/10".to_string(), 1.0));
+        assert_eq!(series[3 * 24 + 11], ("Thu/11".to_string(), 1.0));
+        assert_eq!(series.iter().map(|(_, count)| count).sum::<f64>(), 3.0);
+        task.timezone = Some("America/Los_Angeles".to_string());
+        let local_series = process_task(&task, &messages).expect("Processing failed");
+        assert_eq!(local_series[2 * 24 + 2], ("Wed/02".to_string(), 1.0));
+        assert_eq!(local_series[3 * 24 + 3], ("Thu/03".to_string(), 1.0));
+    }
+
+    #[test]
+    fn test_cumulative_count() {
+        let messages = vec![
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "rust"})),
+            message_in_channel("general", json!({"type": "message", "user": "U02", "ts": "1735729200.000200", "text": "rust"})),
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735898400.000300", "text": "rust"})),
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735898400.000400", "text": "go"})),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::CumulativeCount {
+            channel_pattern: "general".to_string(),
+            message_pattern: "rust".to_string(),
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![
+                ("2025-01-01".to_string(), 2.0),
+                ("2025-01-02".to_string(), 2.0),
+                ("2025-01-03".to_string(), 3.0)
+            ]
+        );
+        task.resolution = TimeResolution::HourOfDay;
+        assert!(task.validate().is_err());
+    }
+
+    #[test]
+    fn test_top_posters() {
+        let mut messages = vec![
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "hi"})),
+            message_in_channel("general", json!({"type": "message", "user": "U02", "ts": "1735729200.000200", "text": "hi"})),
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735898400.000300", "text": "hi"})),
+            message_in_channel("general", json!({"type": "message", "user": "U03", "ts": "1735898400.000400", "text": "hi"})),
+            message_in_channel("general", json!({"type": "message", "user": "U03", "ts": "1735898500.000500", "text": "hi"})),
+            message_in_channel("random", json!({"type": "message", "user": "U02", "ts": "1735898600.000600", "text": "hi"})),
+        ];
+        messages[0].user_name = Some("alice".to_string());
+        messages[2].user_name = Some("alice".to_string());
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::TopPosters {
+            channel_pattern: "general".to_string(),
+            top_n: 2,
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("U03".to_string(), 2.0), ("alice".to_string(), 2.0)]
+        );
+        task.metric = plot::Metric::TopPosters {
+            channel_pattern: "general".to_string(),
+            top_n: 0,
+        };
+        assert!(task.validate().is_err());
+    }
+
+    #[test]
+    fn test_top_channels() {
+        let messages = vec![
+            message_in_channel("general", json!({"type": "message", "ts": "1735725600.000100", "text": "deploy"})),
+            message_in_channel("infra", json!({"type": "message", "ts": "1735729200.000200", "text": "deploy"})),
+            message_in_channel("infra", json!({"type": "message", "ts": "1735898400.000300", "text": "deploy again"})),
+            message_in_channel("random", json!({"type": "message", "ts": "1735898400.000400", "text": "lunch"})),
+            message_in_channel("dev", json!({"type": "message", "ts": "1735898500.000500", "text": "deploy"})),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::TopChannels {
+            message_pattern: "deploy".to_string(),
+            top_n: 2,
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("infra".to_string(), 2.0), ("dev".to_string(), 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_word_frequency() {
+        let messages = vec![
+            message_in_channel("general", json!({"type": "message", "ts": "1735725600.000100", "text": "The deploy failed <@U0123ABC>"})),
+            message_in_channel("general", json!({"type": "message", "ts": "1735729200.000200", "text": "Deploy again :tada:"})),
+            message_in_channel("general", json!({"type": "message", "ts": "1735898400.000300", "text": "deploy done, again"})),
+            message_in_channel("random", json!({"type": "message", "ts": "1735898400.000400", "text": "lunch lunch lunch"})),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::WordFrequency {
+            channel_pattern: "general".to_string(),
+            top_n: 3,
+            stopwords: None,
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("deploy".to_string(), 3.0), ("again".to_string(), 2.0), ("done".to_string(), 1.0)]
+        );
+        task.metric = plot::Metric::WordFrequency {
+            channel_pattern: "general".to_string(),
+            top_n: 2,
+            stopwords: Some(vec!["Deploy".to_string()]),
+        };
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("again".to_string(), 2.0), ("done".to_string(), 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_message_length_distribution() {
+        let messages = vec![
+            message_in_channel("general", json!({"type": "message", "ts": "1735725600.000100", "text": "deploy now"})),
+            message_in_channel("general", json!({"type": "message", "ts": "1735729200.000200", "text": "deploy it :tada:"})),
+            message_in_channel("general", json!({"type": "message", "ts": "1735732800.000300", "text": "deploy"})),
+            message_in_channel("general", json!({"type": "message", "ts": "1735898400.000400", "text": "deploy the new release"})),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::MessageLengthDistribution {
+            channel_pattern: "general".to_string(),
+            message_pattern: "deploy".to_string(),
+            unit: None,
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("2025-01-01".to_string(), 10.0), ("2025-01-03".to_string(), 22.0)]
+        );
+        task.metric = plot::Metric::MessageLengthDistribution {
+            channel_pattern: "general".to_string(),
+            message_pattern: "deploy".to_string(),
+            unit: Some(plot::LengthUnit::Words),
+        };
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("2025-01-01".to_string(), 2.0), ("2025-01-03".to_string(), 4.0)]
+        );
+    }
+
+    #[test]
+    fn test_first_response_time() {
+        let messages = vec![
+            message_in_channel(
+                "support",
+                json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "thread_ts": "1735725600.000100",
+                    "text": "login broken"}),
+            ),
+            message_in_channel(
+                "support",
+                json!({"type": "message", "user": "U01", "ts": "1735725660.000200", "thread_ts": "1735725600.000100",
+                    "text": "still broken"}),
+            ),
+            message_in_channel(
+                "support",
+                json!({"type": "message", "user": "U02", "ts": "1735726200.000300", "thread_ts": "1735725600.000100",
+                    "text": "looking into it"}),
+            ),
+            message_in_channel(
+                "support",
+                json!({"type": "message", "user": "U03", "ts": "1735729200.000400", "thread_ts": "1735729200.000400",
+                    "text": "vpn down"}),
+            ),
+            message_in_channel(
+                "support",
+                json!({"type": "message", "user": "U02", "ts": "1735731000.000500", "thread_ts": "1735729200.000400",
+                    "text": "fixed"}),
+            ),
+            message_in_channel("support", json!({"type": "message", "user": "U03", "ts": "1735812000.000600", "text": "thanks"})),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::FirstResponseTime {
+            channel_pattern: "support".to_string(),
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![("median/2025-01-01".to_string(), 20.0), ("p90/2025-01-01".to_string(), 28.0)]
+        );
+    }
+
+    #[test]
+    fn test_membership_growth() {
+        let messages = vec![
+            message_in_channel("general", json!({"type": "message", "subtype": "channel_join", "user": "U01", "ts": "1735725600.000100", "text": "<@U01> has joined the channel"})),
+            message_in_channel("general", json!({"type": "message", "subtype": "channel_join", "user": "U02", "ts": "1735729200.000200", "text": "<@U02> has joined the channel"})),
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735729300.000300", "text": "welcome"})),
+            message_in_channel("general", json!({"type": "message", "subtype": "channel_leave", "user": "U02", "ts": "1735898400.000400", "text": "<@U02> has left the channel"})),
+            message_in_channel("random", json!({"type": "message", "subtype": "channel_join", "user": "U03", "ts": "1735898400.000500", "text": "<@U03> has joined the channel"})),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::MembershipGrowth {
+            channel_pattern: "general".to_string(),
+        };
+        task.skip_output = true;
+        assert_eq!(
+            process_task(&task, &messages).expect("Processing failed"),
+            vec![
+                ("2025-01-01".to_string(), 2.0),
+                ("2025-01-02".to_string(), 2.0),
+                ("2025-01-03".to_string(), 1.0)
+            ]
+        );
+        task.resolution = TimeResolution::HourOfDay;
+        assert!(task.validate().is_err());
+    }
+
+    #[test]
+    fn test_exclude_bots() {
+        let messages = vec![
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "deploy done"})),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "user": "U02", "bot_id": "B01", "ts": "1735725700.000200", "text": "deploy started"}),
+            ),
+            message_in_channel(
+                "general",
+                json!({"type": "message", "subtype": "bot_message", "bot_id": "B02", "ts": "1735725800.000300",
+                    "text": "deploy reminder"}),
+            ),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.excluded_subtypes = Some(vec![]);
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "general", "deploy"),
+            vec![("2025-01-01".to_string(), 3)]
+        );
+        task.exclude_bots = Some(true);
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "general", "deploy"),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_user_pattern() {
+        let mut messages = vec![
+            message_in_channel("general", json!({"type": "message", "user": "U01", "ts": "1735725600.000100", "text": "deploy"})),
+            message_in_channel("general", json!({"type": "message", "user": "U02", "ts": "1735725700.000200", "text": "deploy"})),
+            message_in_channel("general", json!({"type": "message", "user": "U03", "ts": "1735725800.000300", "text": "deploy"})),
+            message_in_channel("general", json!({"type": "message", "ts": "1735725900.000400", "text": "deploy"})),
+        ];
+        messages[0].user_name = Some("alice".to_string());
+        let mut task = task(TimeResolution::Daily);
+        task.user_pattern = Some("alice".to_string());
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "general", "deploy"),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+        task.user_pattern = Some("U02".to_string());
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "general", "deploy"),
+            vec![("2025-01-01".to_string(), 1)]
+        );
+        task.match_mode = Some(plot::MatchMode::Regex);
+        task.user_pattern = Some("^(alice|U03)$".to_string());
+        assert_eq!(
+            filter_and_count_messages(&messages, &task, "general", "deploy"),
+            vec![("2025-01-01".to_string(), 2)]
+        );
+        task.user_pattern = Some("(".to_string());
+        assert!(task.validate().is_err());
+    }
+
+    #[test]
+    fn test_active_hours_in_time_zone() {
+        // 10:00 and 11:00 UTC, i.e. 02:00 and 03:00 in Los Angeles
+        let messages = vec![
+            message_in_channel("general", json!({"type": "message", "ts": "1735725600.000100", "text": "hi"})),
+            message_in_channel("general", json!({"type": "message", "ts": "1735729200.000200", "text": "hi"})),
+        ];
+        let mut task = task(TimeResolution::Daily);
+        task.metric = plot::Metric::ActiveHoursWindow {
+            channel_pattern: "general".to_string(),
+            coverage: None,
+        };
+        task.skip_output = true;
+        let utc_counts = process_task(&task, &messages).expect("Processing failed");
+        assert_eq!((utc_counts[10].1, utc_counts[11].1), (1.0, 1.0));
+        task.timezone = Some("America/Los_Angeles".to_string());
+        let local_counts = process_task(&task, &messages).expect("Processing failed");
+        assert_eq!(local_counts[2], ("02".to_string(), 1.0));
+        assert_eq!(local_counts[3], ("03".to_string(), 1.0));
+    }
+
+    #[test]
+    fn test_fill_empty_hourly_and_weekly_buckets() {
+        let hourly_counts = fill_empty_buckets(
+            vec![("2024-12-31 22:00".to_string(), 1), ("2025-01-01 01:00".to_string(), 2)],
+            &TimeResolution::Hourly,
+        );
+        assert_eq!(
+            hourly_counts,
+            vec![
+                ("2024-12-31 22:00".to_string(), 1),
+                ("2024-12-31 23:00".to_string(), 0),
+                ("2025-01-01 00:00".to_string(), 0),
+                ("2025-01-01 01:00".to_string(), 2),
+            ]
+        );
+        let weekly_counts = fill_empty_buckets(
+            vec![("2020-W52".to_string(), 1), ("2021-W02".to_string(), 3)],
+            &TimeResolution::Weekly,
+        );
+        assert_eq!(
+            weekly_counts,
+            vec![
+                ("2020-W52".to_string(), 1),
+                ("2020-W53".to_string(), 0),
+                ("2021-W01".to_string(), 0),
+                ("2021-W02".to_string(), 3),
+            ]
+        );
+
+        // Months of hourly buckets are filled in completely, one bucket per hour
+        let long_counts = fill_empty_buckets(
+            vec![("2025-01-01 00:00".to_string(), 1), ("2025-01-31 23:00".to_string(), 1)],
+            &TimeResolution::Hourly,
+        );
+        assert_eq!(long_counts.len(), 31 * 24);
+        assert_eq!(long_counts.iter().map(|(_, count)| count).sum::<usize>(), 2);
+        assert!(long_counts.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+}
@@ -1,17 +1,23 @@
 /// slackrs: a simple command-line tool to create plots from Slack data exports.
 use clap::Parser;
-use slackrs::{plot, slack, plot::PlotTask, slack::MessageInChannel};
-use std::{fs, io::Error, io::ErrorKind, path::PathBuf, result::Result};
+use slackrs::{ErrorPolicy, output, plot, slack, plot::PlotTask, slack::MessageInChannel};
+use std::{fs, io::Error, io::ErrorKind, path::PathBuf, result::Result, time::Instant};
 
 #[derive(Parser)]
 struct Cli {
     #[arg(
         short = 'i',
         long = "input-file",
-        help = "The input file to analyze, in the ZIP format provided by Slack's export."
+        help = "The input to analyze: the ZIP file provided by Slack's export, or a directory it was extracted into."
     )]
     input_file: PathBuf,
 
+    #[arg(
+        long = "compare-file",
+        help = "Optional second export (B, a ZIP file or directory) to compare the input (A) with, plotting the difference B - A per task."
+    )]
+    compare_file: Option<PathBuf>,
+
     #[arg(
         short = 'o',
         long = "output-dir",
@@ -27,15 +33,100 @@ struct Cli {
         help = "The JSON file with the tasks to run (see README for examples)."
     )]
     task_file: PathBuf,
+
+    #[arg(
+        long = "sqlite",
+        help = "Optional SQLite database file to store the computed series in (table 'series')."
+    )]
+    sqlite: Option<PathBuf>,
+
+    #[arg(
+        long = "events",
+        help = "Optional JSON file with events (objects with 'date' and 'label') to annotate in all plots."
+    )]
+    events: Option<PathBuf>,
+
+    #[arg(
+        long = "prometheus",
+        help = "Optional file to write the headline values of all tasks to, in the Prometheus text format."
+    )]
+    prometheus: Option<PathBuf>,
+
+    #[arg(
+        long = "dashboard",
+        help = "Optional HTML file to write a dashboard with all plots and their headline numbers to."
+    )]
+    dashboard: Option<PathBuf>,
+
+    #[arg(
+        long = "report",
+        help = "Optional JSON file to write a report of the run to, with the match count, duration, and error (if any) of each task."
+    )]
+    report: Option<PathBuf>,
+
+    #[arg(
+        long = "dump-messages",
+        help = "Optional CSV file to write all messages of the input to (channel, time, user, and text), regardless of the tasks."
+    )]
+    dump_messages: Option<PathBuf>,
+
+    #[arg(
+        long = "threads",
+        help = "Optional number of threads to process tasks with (1 processes them sequentially); defaults to one per CPU."
+    )]
+    threads: Option<usize>,
+
+    #[arg(
+        long = "cache",
+        help = "Optional state file to remember computed series in, so that unchanged charts are not rendered again."
+    )]
+    cache: Option<PathBuf>,
+
+    #[arg(
+        long = "theme",
+        help = "Optional JSON file with named colors and styling defaults (background, font, grid) for all tasks."
+    )]
+    theme: Option<PathBuf>,
+
+    #[arg(
+        long = "timezone",
+        help = "Optional IANA time zone to bucket messages in, e.g. 'America/Los_Angeles', for tasks without their own; defaults to UTC."
+    )]
+    timezone: Option<String>,
+
+    #[arg(
+        long = "csv-only",
+        help = "Only write the CSV files of all tasks, without rendering any images (which is faster)."
+    )]
+    csv_only: bool,
+
+    #[arg(
+        long = "on-error",
+        default_value = "continue",
+        help = "What to do if a task fails: 'continue' with the other tasks, 'retry' the task once, or 'abort' the run."
+    )]
+    on_error: ErrorPolicy,
 }
 
 impl Cli {
     fn validate(self: &Cli) -> Result<(), Error> {
-        if !self.input_file.is_file() {
+        if !self.input_file.exists() {
             Err(Error::new(
                 ErrorKind::InvalidInput,
-                format!("The input file '{:?}' is not a file.", self.input_file),
+                format!("The input '{:?}' is neither a file nor a directory.", self.input_file),
             ))
+        } else if self.compare_file.as_ref().is_some_and(|file| !file.exists()) {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("The export to compare with '{:?}' is neither a file nor a directory.", self.compare_file),
+            ))
+        } else if self.report.is_some() && (self.compare_file.is_some() || self.cache.is_some()) {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                "A report can neither be written when comparing exports nor when using a cache.",
+            ))
+        } else if let Some(Err(error)) = self.timezone.as_deref().map(plot::parse_time_zone) {
+            Err(Error::new(ErrorKind::InvalidInput, error.to_string()))
         } else if !self.task_file.is_file() {
             Err(Error::new(
                 ErrorKind::InvalidInput,
@@ -59,6 +150,7 @@ impl Cli {
 }
 
 fn main() {
+    let start = Instant::now();
     let args = Cli::parse();
     let validation_result = args.validate();
     if validation_result.is_err() {
@@ -68,17 +160,89 @@ fn main() {
         );
     } else {
         // Start with reading tasks file, as this is faster and more likely to fail
-        let tasks: Vec<PlotTask> = plot::read_tasks_from_file(args.task_file.to_str().unwrap(), &args.output_dir)
+        let mut tasks: Vec<PlotTask> = plot::read_tasks_from_file(args.task_file.to_str().unwrap(), &args.output_dir)
         .expect("Failed to read tasks from file");
+        if let Some(events_file) = &args.events {
+            let events = plot::read_events_from_file(events_file).expect("Failed to read events from file");
+            tasks = tasks.iter().map(|task| task.with_events(&events)).collect();
+        }
+        if let Some(timezone) = &args.timezone {
+            tasks = tasks.iter().map(|task| task.with_timezone(timezone)).collect();
+        }
+        if args.csv_only {
+            tasks = tasks.iter().map(PlotTask::without_image).collect();
+        }
+        if let Some(theme_file) = &args.theme {
+            let theme = plot::read_theme_from_file(theme_file).expect("Failed to read theme from file");
+            tasks = tasks.iter().map(|task| task.with_theme(&theme)).collect();
+        }
         println!(
             "Found {} tasks in task file '{:?}'.",
             tasks.len(),
             args.task_file.file_name().unwrap()
         );
 
-        let messages: Vec<MessageInChannel> = slack::read_zip_contents(&args.input_file);
-        let _ = slackrs::process_tasks(&tasks, &messages);
-        println!("Done.");
+        let messages: Vec<MessageInChannel> = slack::read_export_contents(&args.input_file);
+        if let Some(dump_messages) = &args.dump_messages {
+            output::write_messages(dump_messages, &messages).expect("Failed to write messages");
+        }
+        if tasks.iter().any(|task| task.per_member.unwrap_or(false) || task.filters_channels()) {
+            let channels = slack::read_channels(&args.input_file);
+            let channel_members = slack::members_per_channel(&channels);
+            tasks = tasks
+                .iter()
+                .map(|task| task.with_channel_members(&channel_members).with_channels(&channels))
+                .collect();
+        }
+        let compared_messages: Option<Vec<MessageInChannel>> =
+            args.compare_file.as_ref().map(slack::read_export_contents);
+        let process = || match (&compared_messages, &args.cache) {
+            (Some(compared_messages), _) => {
+                slackrs::process_tasks_diff(&tasks, &messages, compared_messages, args.on_error)
+            }
+            (None, Some(cache_file)) => {
+                slackrs::process_tasks_cached(&tasks, &messages, cache_file, args.on_error)
+            }
+            (None, None) => slackrs::process_tasks_with_outcomes(&tasks, &messages, args.on_error).map(|outcomes| {
+                if let Some(report) = &args.report {
+                    output::write_report(report, &outcomes, start.elapsed()).expect("Failed to write report");
+                }
+                slackrs::successful_series(outcomes)
+            }),
+        };
+        let results = match args.threads {
+            Some(threads) => slackrs::run_with_threads(threads, process).and_then(|result| result),
+            None => process(),
+        };
+        let results = match results {
+            Ok(results) => results,
+            Err(error) => {
+                eprintln!("{}", error);
+                println!("Done: run aborted, not all of {} tasks succeeded.", tasks.len());
+                std::process::exit(1);
+            }
+        };
+        for result in &results {
+            println!(
+                "Trend of '{}' over the last {} buckets: {}.",
+                result.task.output_file_name,
+                output::TREND_WINDOW,
+                output::series_trend(&result.series)
+            );
+        }
+        if let Some(sqlite) = &args.sqlite {
+            output::write_sqlite(sqlite, &results).expect("Failed to write to SQLite database");
+        }
+        if let Some(prometheus) = &args.prometheus {
+            output::write_prometheus(prometheus, &results).expect("Failed to write Prometheus file");
+        }
+        if let Some(dashboard) = &args.dashboard {
+            output::write_dashboard(dashboard, &results).expect("Failed to write dashboard");
+        }
+        println!("Done: {} of {} tasks succeeded.", results.len(), tasks.len());
+        if results.len() < tasks.len() {
+            std::process::exit(1);
+        }
     }
 }
 
@@ -0,0 +1,438 @@
+use crate::plot::OutputFormat;
+use crate::slack::MessageInChannel;
+use crate::stats::{self, Trend};
+use crate::{TaskOutcome, TaskSeries};
+use chrono::SecondsFormat;
+use csv::Writer;
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use std::{error::Error, fmt::Write, fs, path::Path, time::Duration};
+
+/// Writes the series of all tasks into the `series` table of a SQLite database (created if needed).
+///
+/// Rows of tasks with the same output file name are replaced, so that re-running tasks does not duplicate data.
+pub fn write_sqlite(db_path: &Path, results: &[TaskSeries]) -> Result<(), Box<dyn Error>> {
+    let mut connection = Connection::open(db_path)?;
+    connection.execute(
+        "CREATE TABLE IF NOT EXISTS series (
+            task TEXT NOT NULL,
+            metric TEXT NOT NULL,
+            label TEXT NOT NULL,
+            value REAL NOT NULL
+        )",
+        [],
+    )?;
+    let transaction = connection.transaction()?;
+    for result in results {
+        transaction.execute(
+            "DELETE FROM series WHERE task = ?1",
+            params![result.task.output_file_name],
+        )?;
+        for (label, value) in &result.series {
+            transaction.execute(
+                "INSERT INTO series (task, metric, label, value) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    result.task.output_file_name,
+                    result.task.metric.name(),
+                    label,
+                    value
+                ],
+            )?;
+        }
+    }
+    transaction.commit()?;
+    println!(
+        "Wrote series of {} tasks to SQLite database '{}'.",
+        results.len(),
+        db_path.display()
+    );
+    Ok(())
+}
+
+/// Writes the headline values of all tasks (the value of the latest time bucket and the total over all buckets)
+/// as gauges in the Prometheus text exposition format, labeled with the task, metric, channel, and pattern.
+///
+/// Tasks without any values are left out.
+pub fn write_prometheus(path: &Path, results: &[TaskSeries]) -> Result<(), Box<dyn Error>> {
+    let results: Vec<&TaskSeries> = results.iter().filter(|x| !x.series.is_empty()).collect();
+    let mut text = String::new();
+    write_gauge(
+        &mut text,
+        "slackrs_latest_value",
+        "Value of the latest time bucket of the task's series.",
+        &results,
+        |series| series.last().map_or(0.0, |(_, value)| *value),
+    )?;
+    write_gauge(
+        &mut text,
+        "slackrs_total",
+        "Sum of the values of all time buckets of the task's series.",
+        &results,
+        |series| series.iter().map(|(_, value)| value).sum(),
+    )?;
+    fs::write(path, text)?;
+    println!(
+        "Wrote headline values of {} tasks to Prometheus file '{}'.",
+        results.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Writes a Prometheus gauge with one sample per task.
+fn write_gauge<F>(
+    text: &mut String,
+    name: &str,
+    help: &str,
+    results: &[&TaskSeries],
+    value_of: F,
+) -> Result<(), std::fmt::Error>
+where
+    F: Fn(&[(String, f64)]) -> f64,
+{
+    writeln!(text, "# HELP {} {}\n# TYPE {} gauge", name, help, name)?;
+    for result in results {
+        let metric = &result.task.metric;
+        writeln!(
+            text,
+            "{}{{task=\"{}\",metric=\"{}\",channel=\"{}\",pattern=\"{}\"}} {}",
+            name,
+            escape_label_value(&result.task.output_file_name),
+            metric.name(),
+            escape_label_value(metric.channel_pattern().unwrap_or_default()),
+            escape_label_value(metric.message_pattern().unwrap_or_default()),
+            value_of(&result.series)
+        )?;
+    }
+    Ok(())
+}
+
+/// Escapes backslashes, quotes, and line breaks in Prometheus label values.
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Writes an HTML page showing the image of each task, together with some headline numbers of its series.
+///
+/// Images inside the dashboard's directory are referenced relative to it, so the directory can be moved as a whole.
+pub fn write_dashboard(dashboard_path: &Path, results: &[TaskSeries]) -> Result<(), Box<dyn Error>> {
+    let dashboard_dir = dashboard_path.parent().unwrap_or(Path::new(""));
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Slack Dashboard</title>\n</head>\n<body>\n<h1>Slack Dashboard</h1>\n",
+    );
+    for result in results {
+        let image_path = Path::new(&result.task.output_file_name);
+        let image_src = image_path.strip_prefix(dashboard_dir).unwrap_or(image_path);
+        let title = format!(
+            "{} ({})",
+            result.task.metric.name(),
+            image_path.file_name().unwrap_or_default().to_string_lossy()
+        );
+        writeln!(html, "<section>\n<h2>{}</h2>", escape_html(&title))?;
+        let src = escape_html(&image_src.to_string_lossy());
+        match result.task.output_format() {
+            // Interactive charts are pages of their own, so they are embedded as frames
+            OutputFormat::Html => writeln!(
+                html,
+                "<iframe src=\"{}\" title=\"{}\" style=\"width: 100%; height: 600px; border: none\"></iframe>",
+                src,
+                escape_html(&title)
+            )?,
+            _ => writeln!(html, "<img src=\"{}\" alt=\"{}\">", src, escape_html(&title))?,
+        }
+        writeln!(html, "<ul>\n{}</ul>\n</section>", summary_items(&result.series))?;
+    }
+    html.push_str("</body>\n</html>\n");
+    fs::write(dashboard_path, html)?;
+    println!(
+        "Wrote dashboard with {} tasks to '{}'.",
+        results.len(),
+        dashboard_path.display()
+    );
+    Ok(())
+}
+
+/// Writes all messages into a CSV file, with their channel, time (ISO 8601), user ID and name (if any), and text.
+pub fn write_messages(path: &Path, messages: &[MessageInChannel]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = Writer::from_path(path)?;
+    wtr.write_record(["channel", "time", "user", "user_name", "text"])?;
+    for message in messages {
+        wtr.write_record([
+            message.channel.as_str(),
+            &message
+                .message
+                .time_precise()
+                .map(|time| time.to_rfc3339_opts(SecondsFormat::Micros, true))
+                .unwrap_or_default(),
+            message.message.user().unwrap_or(""),
+            message.user_name.as_deref().unwrap_or(""),
+            &message.message.text,
+        ])?;
+    }
+    wtr.flush()?;
+    println!("Wrote {} messages to '{}'.", messages.len(), path.display());
+    Ok(())
+}
+
+/// A machine-readable summary of a run, written as JSON.
+#[derive(Serialize)]
+struct RunReport<'a> {
+    total_seconds: f64,
+    tasks: Vec<TaskReport<'a>>,
+}
+
+/// The summary of a single task within a `RunReport`.
+#[derive(Serialize)]
+struct TaskReport<'a> {
+    metric: &'static str,
+    output_file: &'a str,
+    match_count: usize,
+    duration_seconds: f64,
+    error: Option<&'a str>,
+}
+
+/// Writes a JSON report of the run, with the metric, match count, duration, output file, and error (if any)
+/// of each task, as well as the total duration of the run.
+pub fn write_report(report_path: &Path, outcomes: &[TaskOutcome], total: Duration) -> Result<(), Box<dyn Error>> {
+    let report = RunReport {
+        total_seconds: total.as_secs_f64(),
+        tasks: outcomes
+            .iter()
+            .map(|outcome| TaskReport {
+                metric: outcome.task.metric.name(),
+                output_file: &outcome.task.output_file_name,
+                match_count: outcome.match_count,
+                duration_seconds: outcome.duration.as_secs_f64(),
+                error: outcome.result.as_ref().err().map(String::as_str),
+            })
+            .collect(),
+    };
+    fs::write(report_path, serde_json::to_string_pretty(&report)?)?;
+    println!(
+        "Wrote report of {} tasks to '{}'.",
+        outcomes.len(),
+        report_path.display()
+    );
+    Ok(())
+}
+
+/// The number of most recent time buckets to classify the trend of a series by.
+pub const TREND_WINDOW: usize = 8;
+
+/// The relative change over the trend window (compared to the mean) up to which a series counts as flat.
+const TREND_TOLERANCE: f64 = 0.05;
+
+/// Classifies the trend of a series over its last `TREND_WINDOW` values.
+pub fn series_trend(series: &[(String, f64)]) -> Trend {
+    let values: Vec<f64> = series.iter().map(|(_, value)| *value).collect();
+    stats::trend(&values, TREND_WINDOW, TREND_TOLERANCE)
+}
+
+/// Lists the headline numbers of a series as HTML list items.
+fn summary_items(series: &[(String, f64)]) -> String {
+    let Some((last_label, last_value)) = series.last() else {
+        return "<li>No data</li>\n".to_string();
+    };
+    let values: Vec<f64> = series.iter().map(|(_, value)| *value).collect();
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    format!(
+        "<li>Time buckets: {}</li>\n<li>Min: {:.2}</li>\n<li>Max: {:.2}</li>\n<li>Mean: {:.2}</li>\n<li>Latest ({}): {:.2}</li>\n<li>Trend (last {} buckets): {}</li>\n",
+        values.len(),
+        min,
+        max,
+        mean,
+        escape_html(last_label),
+        last_value,
+        TREND_WINDOW,
+        series_trend(series)
+    )
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plot::{Metric, PlotTask, TimeResolution};
+
+    fn task_series(output_file_name: &str, series: &[(&str, f64)]) -> TaskSeries {
+        TaskSeries {
+            task: PlotTask::new(
+                Metric::MentionCount {
+                    channel_pattern: "".to_string(),
+                    message_pattern: "".to_string(),
+                },
+                TimeResolution::Daily,
+                output_file_name,
+            ),
+            series: series
+                .iter()
+                .map(|(label, value)| (label.to_string(), *value))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_write_sqlite() {
+        let db_path = std::env::temp_dir().join("slackrs-test-write-sqlite.sqlite");
+        let _ = std::fs::remove_file(&db_path);
+        let results = vec![
+            task_series("a.png", &[("2025-01", 1.0), ("2025-02", 2.0)]),
+            task_series("b.png", &[("2025-01", 3.0)]),
+        ];
+        write_sqlite(&db_path, &results).expect("Writing to SQLite failed");
+        // Writing again must replace, not duplicate, the rows
+        write_sqlite(&db_path, &results).expect("Writing to SQLite failed");
+
+        let connection = Connection::open(&db_path).unwrap();
+        let count = |task: &str| -> i64 {
+            connection
+                .query_row(
+                    "SELECT COUNT(*) FROM series WHERE task = ?1 AND metric = 'MentionCount'",
+                    params![task],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        assert_eq!(count("a.png"), 2);
+        assert_eq!(count("b.png"), 1);
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[test]
+    fn test_write_dashboard() {
+        let dir = std::env::temp_dir().join("slackrs-test-write-dashboard");
+        std::fs::create_dir_all(&dir).unwrap();
+        let image_a = dir.join("a.png");
+        let results = vec![
+            task_series(image_a.to_str().unwrap(), &[("2025-01", 1.0), ("2025-02", 3.0)]),
+            task_series("elsewhere/b.png", &[]),
+        ];
+        let dashboard_path = dir.join("dashboard.html");
+        write_dashboard(&dashboard_path, &results).expect("Writing the dashboard failed");
+
+        let html = std::fs::read_to_string(&dashboard_path).unwrap();
+        assert!(html.contains("<img src=\"a.png\""));
+        assert!(html.contains("<img src=\"elsewhere/b.png\""));
+        assert!(html.contains("<li>Latest (2025-02): 3.00</li>"));
+        assert!(html.contains("<li>Trend (last 8 buckets): rising</li>"));
+        assert!(html.contains("<li>No data</li>"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_prometheus() {
+        let path = std::env::temp_dir().join("slackrs-test-write-prometheus.prom");
+        let mut quoted = task_series("b.png", &[("2025-01", 3.0)]);
+        quoted.task.metric = Metric::MentionCount {
+            channel_pattern: "general".to_string(),
+            message_pattern: "say \"hi\"".to_string(),
+        };
+        let results = vec![
+            task_series("a.png", &[("2025-01", 1.0), ("2025-02", 2.5)]),
+            quoted,
+            task_series("empty.png", &[]),
+        ];
+        write_prometheus(&path, &results).expect("Writing Prometheus file failed");
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("# TYPE slackrs_latest_value gauge\n"));
+        assert!(text.contains(
+            "slackrs_latest_value{task=\"a.png\",metric=\"MentionCount\",channel=\"\",pattern=\"\"} 2.5\n"
+        ));
+        assert!(text.contains(
+            "slackrs_total{task=\"a.png\",metric=\"MentionCount\",channel=\"\",pattern=\"\"} 3.5\n"
+        ));
+        assert!(text.contains("channel=\"general\",pattern=\"say \\\"hi\\\"\"} 3\n"));
+        assert!(!text.contains("empty.png"));
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let (series, value) = line.rsplit_once(' ').expect("Missing value");
+            assert!(series.starts_with("slackrs_") && series.ends_with('}'));
+            assert!(value.parse::<f64>().is_ok());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_write_report() {
+        let path = std::env::temp_dir().join("slackrs-test-write-report.json");
+        let counting_task = task_series("counts.png", &[]).task.without_output();
+        let mut failing_task = counting_task.clone();
+        failing_task.output_file_name = "failing.png".to_string();
+        failing_task.metric = Metric::MessageLengthPercentile {
+            channel_pattern: "".to_string(),
+            message_pattern: "".to_string(),
+            percentile: 200.0,
+        };
+        let messages = vec![crate::slack::MessageInChannel::new(
+            "general/2025-01-01.json",
+            serde_json::from_value(serde_json::json!({"type": "message", "ts": "1735725600.000100", "text": "hi"}))
+                .unwrap(),
+        )];
+        let outcomes = crate::process_tasks(&[counting_task, failing_task], &messages).expect("Processing failed");
+        write_report(&path, &outcomes, Duration::from_millis(1500)).expect("Writing the report failed");
+
+        let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(report["total_seconds"], 1.5);
+        let tasks = report["tasks"].as_array().expect("Missing tasks");
+        assert_eq!(tasks.len(), 2);
+        for task in tasks {
+            assert!(task["duration_seconds"].as_f64().expect("Missing duration") >= 0.0);
+        }
+        assert_eq!(tasks[0]["metric"], "MentionCount");
+        assert_eq!(tasks[0]["match_count"], 1);
+        assert_eq!(tasks[0]["output_file"], "counts.png");
+        assert!(tasks[0]["error"].is_null());
+        assert_eq!(tasks[1]["metric"], "MessageLengthPercentile");
+        assert!(tasks[1]["error"].as_str().expect("Missing error").contains("Percentile"));
+        // The invalid task fails before matching any messages
+        assert_eq!(tasks[1]["match_count"], 0);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_write_messages() {
+        let path = std::env::temp_dir().join("slackrs-test-write-messages.csv");
+        let message = |file_path: &str, message: serde_json::Value| {
+            MessageInChannel::new(file_path, serde_json::from_value(message).unwrap())
+        };
+        let mut messages = vec![
+            message(
+                "general/2025-01-01.json",
+                serde_json::json!({"type": "message", "user": "U01", "ts": "1735725600.000100",
+                    "text": "first line, with comma\nsecond \"line\""}),
+            ),
+            message(
+                "random/2025-01-01.json",
+                serde_json::json!({"type": "message", "ts": "1735729200.000000", "text": "no user"}),
+            ),
+        ];
+        messages[0].user_name = Some("Alice".to_string());
+        write_messages(&path, &messages).expect("Writing messages failed");
+
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        assert_eq!(reader.headers().unwrap(), vec!["channel", "time", "user", "user_name", "text"]);
+        let records: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(
+            records,
+            vec![
+                csv::StringRecord::from(vec![
+                    "general",
+                    "2025-01-01T10:00:00.000100Z",
+                    "U01",
+                    "Alice",
+                    "first line, with comma\nsecond \"line\"",
+                ]),
+                csv::StringRecord::from(vec!["random", "2025-01-01T11:00:00.000000Z", "", "", "no user"]),
+            ]
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,11 +1,30 @@
+use crate::backend::FileBackend;
+use crate::sink::{ArtifactSink, FileSink};
+use crate::slack::{self, Matcher};
+use crate::stats;
+use chrono::{
+    DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeDelta,
+    TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
 use csv::Writer;
+use image::{ColorType, ImageEncoder, codecs::png::PngEncoder};
+use plotters::coord::Shift;
+use plotters::coord::combinators::{BuildNestedCoord, NestedValue};
 use plotters::prelude::*;
-use serde::Deserialize;
-use std::{collections::HashSet, error::Error, fs, path::PathBuf};
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    error::Error,
+    fs,
+    path::Path,
+};
 
 const DEFAULT_IMAGE_DIM: (u32, u32) = (2048, 1024);
 
-#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(Deserialize, Debug, PartialEq, Clone)]
 pub enum Metric {
     MentionCount {
         channel_pattern: String,
@@ -16,23 +35,690 @@ pub enum Metric {
         message_pattern1: String,
         message_pattern2: String,
     },
+    EditRate {
+        channel_pattern: String,
+        message_pattern: String,
+    },
+    LinkCount {
+        channel_pattern: String,
+        url_pattern: String,
+    },
+    BurstCount {
+        channel_pattern: String,
+        message_pattern: String,
+        burst_factor: f64,
+    },
+    ActiveHoursWindow {
+        channel_pattern: String,
+        /// The fraction of messages the window needs to cover (defaults to 0.9).
+        coverage: Option<f64>,
+    },
+    AverageThreadParticipants {
+        channel_pattern: String,
+    },
+    CumulativeUsers {
+        channel_pattern: String,
+    },
+    /// The number of channel members over time (summed over all matching channels), as line chart.
+    /// Starts at zero, as it is the running total of the joins minus the leaves within the export.
+    MembershipGrowth {
+        channel_pattern: String,
+    },
+    /// The running total of matching messages up to (and including) each time bucket, as line chart.
+    CumulativeCount {
+        channel_pattern: String,
+        message_pattern: String,
+    },
+    MaxReplyDepth {
+        channel_pattern: String,
+    },
+    ParticipationGini {
+        channel_pattern: String,
+        message_pattern: String,
+    },
+    EmojiOnlyMessageCount {
+        channel_pattern: String,
+    },
+    CrossPostCount {
+        message_pattern: String,
+        /// The maximal time between the posts of a cross-post, in minutes (defaults to 60).
+        window_minutes: Option<u32>,
+    },
+    /// Distinct users who posted per week, regardless of the task's resolution.
+    WeeklyActiveUsers {
+        channel_pattern: String,
+        /// If set, only messages containing this pattern count as activity.
+        message_pattern: Option<String>,
+    },
+    /// The given percentile (0-100) of the message lengths (in characters) per time bucket.
+    MessageLengthPercentile {
+        channel_pattern: String,
+        message_pattern: String,
+        percentile: f64,
+    },
+    /// The distribution of the message lengths per time bucket, as box plot.
+    MessageLengthDistribution {
+        channel_pattern: String,
+        message_pattern: String,
+        /// The unit to measure message lengths in (defaults to characters).
+        unit: Option<LengthUnit>,
+    },
+    /// Counts the messages matching any pattern of a group, with one series per (named) group.
+    PatternGroupCount {
+        channel_pattern: String,
+        pattern_groups: BTreeMap<String, Vec<String>>,
+    },
+    /// The median time (in minutes) until a message gets its first reaction.
+    /// Only works for exports that include reaction timestamps, which standard Slack exports do not.
+    TimeToFirstReaction {
+        channel_pattern: String,
+    },
+    /// Reactions with the given emoji (e.g. `tada`), counted in the time bucket of the message they react to.
+    ReactionCount {
+        channel_pattern: String,
+        emoji: String,
+    },
+    /// Messages repeating the exact text of the same user's earlier message within a short time window.
+    DuplicateMessageCount {
+        channel_pattern: String,
+        /// The maximal time between the original and its duplicate, in minutes (defaults to 10).
+        window_minutes: Option<u32>,
+    },
+    /// Messages mentioning both patterns (as an absolute count, unlike `StringMessageCountRatio`).
+    CoOccurrenceCount {
+        channel_pattern: String,
+        pattern_a: String,
+        pattern_b: String,
+    },
+    /// The longest period (in days) without any message, per matching channel.
+    LongestSilence {
+        channel_pattern: String,
+    },
+    /// Mentions of each term per time bucket, with one series per term (e.g. to see one term replacing another).
+    TermAdoption {
+        channel_pattern: String,
+        terms: Vec<String>,
+    },
+    /// The average number of messages new users post within `days` (defaults to 7) after their first message,
+    /// per cohort (the time bucket of their first message, as join dates are not available).
+    OnboardingActivity {
+        channel_pattern: String,
+        days: Option<u32>,
+    },
+    /// The shares of regular messages, joins, bot messages, file shares, and other events, as a pie chart.
+    MessageTypeComposition {
+        channel_pattern: String,
+    },
+    /// The `top_n` messages with the most reactions, written as a CSV table instead of a chart
+    /// (so the task's output file should end with `.csv`).
+    TopReactedMessages {
+        channel_pattern: String,
+        top_n: usize,
+    },
+    /// The `top_n` users with the most messages (by display name), as horizontal bar chart.
+    TopPosters {
+        channel_pattern: String,
+        top_n: usize,
+    },
+    /// The `top_n` most frequent words in message texts, as horizontal bar chart.
+    WordFrequency {
+        channel_pattern: String,
+        top_n: usize,
+        /// The words to leave out (case-insensitive); defaults to common English words.
+        stopwords: Option<Vec<String>>,
+    },
+    /// The `top_n` channels with the most matching messages, as horizontal bar chart.
+    TopChannels {
+        message_pattern: String,
+        top_n: usize,
+    },
+    /// The fraction of matching messages containing at least one exclamation mark, as a simple mood proxy.
+    ExclamationRatio {
+        channel_pattern: String,
+        message_pattern: String,
+    },
+    /// The fraction of matching messages written predominantly in uppercase letters, as a simple tone indicator.
+    AllCapsRatio {
+        channel_pattern: String,
+        message_pattern: String,
+    },
+    /// Matching messages per time bucket, stacked by the `top_n` users posting most of them (all others are
+    /// combined as 'other').
+    MentionCountByUser {
+        channel_pattern: String,
+        message_pattern: String,
+        top_n: usize,
+    },
+    /// Matching messages per time bucket for each of several patterns, plotted side by side for comparison.
+    MultiMentionCount {
+        channel_pattern: String,
+        message_patterns: Vec<String>,
+    },
+    /// The fraction of questions (thread roots containing a question mark) that received a thread reply
+    /// from another user within `sla_hours`, per time bucket of the questions.
+    ResponseCoverage {
+        channel_pattern: String,
+        sla_hours: f64,
+    },
+    /// The median and 90th percentile of the minutes until thread roots received their first reply from
+    /// another user, per time bucket of the roots. Roots without such a reply are left out.
+    FirstResponseTime {
+        channel_pattern: String,
+    },
+    /// The number of buckets after the peak of matching messages until their count falls to half of the peak,
+    /// i.e. how quickly a topic fades after a spike. The counts are plotted with the peak and that bucket highlighted.
+    DecayHalfLife {
+        channel_pattern: String,
+        message_pattern: String,
+    },
+    /// Messages sent during quiet hours, i.e. from `start_hour` until before `end_hour` local time
+    /// (wrapping around midnight if `start_hour` is larger, e.g. 20 to 8).
+    /// The time zone is given by its IANA name (e.g. `Europe/Berlin`) and defaults to the task's time zone.
+    QuietHoursCount {
+        channel_pattern: String,
+        start_hour: u32,
+        end_hour: u32,
+        timezone: Option<String>,
+    },
+    /// Messages per day of the week (rows) and hour of the day (columns), in the task's time zone, as heatmap.
+    ActivityHeatmap {
+        channel_pattern: String,
+    },
+    /// Replies in threads, per time bucket of the reply.
+    ThreadReplyCount {
+        channel_pattern: String,
+    },
+    /// The number of distinct users posting matching messages, per time bucket.
+    UniqueUserCount {
+        channel_pattern: String,
+        message_pattern: String,
+    },
+    /// The Jaccard overlap (0-1) between the users who posted and the users who reacted, per time bucket.
+    ReactorPosterOverlap {
+        channel_pattern: String,
+    },
+}
+
+impl Metric {
+    /// Returns the name of the metric, as used in the task file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::MentionCount { .. } => "MentionCount",
+            Metric::StringMessageCountRatio { .. } => "StringMessageCountRatio",
+            Metric::EditRate { .. } => "EditRate",
+            Metric::LinkCount { .. } => "LinkCount",
+            Metric::BurstCount { .. } => "BurstCount",
+            Metric::ActiveHoursWindow { .. } => "ActiveHoursWindow",
+            Metric::AverageThreadParticipants { .. } => "AverageThreadParticipants",
+            Metric::CumulativeUsers { .. } => "CumulativeUsers",
+            Metric::CumulativeCount { .. } => "CumulativeCount",
+            Metric::MembershipGrowth { .. } => "MembershipGrowth",
+            Metric::MaxReplyDepth { .. } => "MaxReplyDepth",
+            Metric::ParticipationGini { .. } => "ParticipationGini",
+            Metric::EmojiOnlyMessageCount { .. } => "EmojiOnlyMessageCount",
+            Metric::CrossPostCount { .. } => "CrossPostCount",
+            Metric::WeeklyActiveUsers { .. } => "WeeklyActiveUsers",
+            Metric::MessageLengthPercentile { .. } => "MessageLengthPercentile",
+            Metric::MessageLengthDistribution { .. } => "MessageLengthDistribution",
+            Metric::PatternGroupCount { .. } => "PatternGroupCount",
+            Metric::TimeToFirstReaction { .. } => "TimeToFirstReaction",
+            Metric::ReactionCount { .. } => "ReactionCount",
+            Metric::DuplicateMessageCount { .. } => "DuplicateMessageCount",
+            Metric::CoOccurrenceCount { .. } => "CoOccurrenceCount",
+            Metric::LongestSilence { .. } => "LongestSilence",
+            Metric::TermAdoption { .. } => "TermAdoption",
+            Metric::TopReactedMessages { .. } => "TopReactedMessages",
+            Metric::TopPosters { .. } => "TopPosters",
+            Metric::TopChannels { .. } => "TopChannels",
+            Metric::WordFrequency { .. } => "WordFrequency",
+            Metric::OnboardingActivity { .. } => "OnboardingActivity",
+            Metric::MessageTypeComposition { .. } => "MessageTypeComposition",
+            Metric::ExclamationRatio { .. } => "ExclamationRatio",
+            Metric::AllCapsRatio { .. } => "AllCapsRatio",
+            Metric::DecayHalfLife { .. } => "DecayHalfLife",
+            Metric::ResponseCoverage { .. } => "ResponseCoverage",
+            Metric::FirstResponseTime { .. } => "FirstResponseTime",
+            Metric::MultiMentionCount { .. } => "MultiMentionCount",
+            Metric::MentionCountByUser { .. } => "MentionCountByUser",
+            Metric::QuietHoursCount { .. } => "QuietHoursCount",
+            Metric::ThreadReplyCount { .. } => "ThreadReplyCount",
+            Metric::ActivityHeatmap { .. } => "ActivityHeatmap",
+            Metric::UniqueUserCount { .. } => "UniqueUserCount",
+            Metric::ReactorPosterOverlap { .. } => "ReactorPosterOverlap",
+        }
+    }
+
+    /// Returns the pattern of the channels the metric considers, if it has one.
+    pub fn channel_pattern(&self) -> Option<&str> {
+        match self {
+            Metric::CrossPostCount { .. } | Metric::TopChannels { .. } => None,
+            Metric::MentionCount { channel_pattern, .. }
+            | Metric::StringMessageCountRatio { channel_pattern, .. }
+            | Metric::EditRate { channel_pattern, .. }
+            | Metric::LinkCount { channel_pattern, .. }
+            | Metric::BurstCount { channel_pattern, .. }
+            | Metric::ActiveHoursWindow { channel_pattern, .. }
+            | Metric::AverageThreadParticipants { channel_pattern }
+            | Metric::CumulativeUsers { channel_pattern }
+            | Metric::CumulativeCount { channel_pattern, .. }
+            | Metric::MembershipGrowth { channel_pattern }
+            | Metric::MaxReplyDepth { channel_pattern }
+            | Metric::ParticipationGini { channel_pattern, .. }
+            | Metric::EmojiOnlyMessageCount { channel_pattern }
+            | Metric::WeeklyActiveUsers { channel_pattern, .. }
+            | Metric::MessageLengthPercentile { channel_pattern, .. }
+            | Metric::MessageLengthDistribution { channel_pattern, .. }
+            | Metric::PatternGroupCount { channel_pattern, .. }
+            | Metric::TimeToFirstReaction { channel_pattern }
+            | Metric::ReactionCount { channel_pattern, .. }
+            | Metric::DuplicateMessageCount { channel_pattern, .. }
+            | Metric::CoOccurrenceCount { channel_pattern, .. }
+            | Metric::LongestSilence { channel_pattern }
+            | Metric::TermAdoption { channel_pattern, .. }
+            | Metric::OnboardingActivity { channel_pattern, .. }
+            | Metric::MessageTypeComposition { channel_pattern }
+            | Metric::TopReactedMessages { channel_pattern, .. }
+            | Metric::TopPosters { channel_pattern, .. }
+            | Metric::WordFrequency { channel_pattern, .. }
+            | Metric::ExclamationRatio { channel_pattern, .. }
+            | Metric::AllCapsRatio { channel_pattern, .. }
+            | Metric::DecayHalfLife { channel_pattern, .. }
+            | Metric::ResponseCoverage { channel_pattern, .. }
+            | Metric::FirstResponseTime { channel_pattern }
+            | Metric::MultiMentionCount { channel_pattern, .. }
+            | Metric::MentionCountByUser { channel_pattern, .. }
+            | Metric::QuietHoursCount { channel_pattern, .. }
+            | Metric::ThreadReplyCount { channel_pattern }
+            | Metric::ActivityHeatmap { channel_pattern }
+            | Metric::UniqueUserCount { channel_pattern, .. }
+            | Metric::ReactorPosterOverlap { channel_pattern } => Some(channel_pattern),
+        }
+    }
+
+    /// Returns the (single) pattern the messages of the metric need to match, if it has one.
+    pub fn message_pattern(&self) -> Option<&str> {
+        match self {
+            Metric::MentionCount { message_pattern, .. }
+            | Metric::EditRate { message_pattern, .. }
+            | Metric::BurstCount { message_pattern, .. }
+            | Metric::ParticipationGini { message_pattern, .. }
+            | Metric::CrossPostCount { message_pattern, .. }
+            | Metric::MessageLengthPercentile { message_pattern, .. }
+            | Metric::MessageLengthDistribution { message_pattern, .. }
+            | Metric::ExclamationRatio { message_pattern, .. }
+            | Metric::AllCapsRatio { message_pattern, .. }
+            | Metric::DecayHalfLife { message_pattern, .. }
+            | Metric::MentionCountByUser { message_pattern, .. }
+            | Metric::CumulativeCount { message_pattern, .. }
+            | Metric::TopChannels { message_pattern, .. }
+            | Metric::UniqueUserCount { message_pattern, .. } => Some(message_pattern),
+            Metric::LinkCount { url_pattern, .. } => Some(url_pattern),
+            Metric::WeeklyActiveUsers { message_pattern, .. } => message_pattern.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns all patterns of the metric, i.e. its channel pattern and all patterns messages are matched against.
+    pub fn patterns(&self) -> Vec<&str> {
+        let mut patterns: Vec<&str> = self.channel_pattern().into_iter().collect();
+        patterns.extend(self.message_patterns());
+        patterns
+    }
+
+    /// Returns all patterns messages are matched against by the metric.
+    pub fn message_patterns(&self) -> Vec<&str> {
+        let mut patterns: Vec<&str> = Vec::new();
+        match self {
+            Metric::StringMessageCountRatio {
+                message_pattern1,
+                message_pattern2,
+                ..
+            } => patterns.extend([message_pattern1.as_str(), message_pattern2.as_str()]),
+            Metric::CoOccurrenceCount {
+                pattern_a,
+                pattern_b,
+                ..
+            } => patterns.extend([pattern_a.as_str(), pattern_b.as_str()]),
+            Metric::TermAdoption { terms, .. } => patterns.extend(terms.iter().map(String::as_str)),
+            Metric::MultiMentionCount { message_patterns, .. } => {
+                patterns.extend(message_patterns.iter().map(String::as_str))
+            }
+            Metric::PatternGroupCount { pattern_groups, .. } => {
+                patterns.extend(pattern_groups.values().flatten().map(String::as_str))
+            }
+            _ => patterns.extend(self.message_pattern()),
+        }
+        patterns
+    }
+
+    /// Checks the metric's parameters, as far as this is possible without any data.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Metric::MessageLengthPercentile { percentile, .. }
+                if !(0.0..=100.0).contains(percentile) =>
+            {
+                Err(format!("Percentile must be between 0 and 100, but is {}.", percentile).into())
+            }
+            Metric::ResponseCoverage { sla_hours, .. } if !(0.0..).contains(sla_hours) => {
+                Err(format!("SLA hours must not be negative, but are {}.", sla_hours).into())
+            }
+            Metric::MultiMentionCount { message_patterns, .. } if message_patterns.is_empty() => {
+                Err("At least one message pattern is required.".into())
+            }
+            Metric::TopPosters { top_n: 0, .. } => Err("At least one poster needs to be shown.".into()),
+            Metric::TopChannels { top_n: 0, .. } => Err("At least one channel needs to be shown.".into()),
+            Metric::WordFrequency { top_n: 0, .. } => Err("At least one word needs to be shown.".into()),
+            Metric::QuietHoursCount {
+                start_hour,
+                end_hour,
+                timezone,
+                ..
+            } => {
+                if *start_hour > 23 || *end_hour > 23 {
+                    return Err(format!(
+                        "Quiet hours must be between 0 and 23, but are {} to {}.",
+                        start_hour, end_hour
+                    )
+                    .into());
+                }
+                timezone.as_deref().map_or(Ok(()), |timezone| parse_time_zone(timezone).map(|_| ()))
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
 pub enum TimeResolution {
+    /// Hours (in the task's time zone), e.g. `2025-01-01 13:00`.
+    Hourly,
     Daily,
+    /// ISO weeks, e.g. `2025-W01`.
+    Weekly,
     Monthly,
     Yearly,
+    /// Hours of the day (in the task's time zone), `00` to `23`, folding all days together.
+    HourOfDay,
+}
+impl TimeResolution {
+    /// Returns the (sortable) label of the time bucket a time belongs to, in the time's time zone.
+    pub fn label<T: TimeZone>(&self, time: &DateTime<T>) -> String
+    where
+        T::Offset: std::fmt::Display,
+    {
+        match self {
+            TimeResolution::Hourly => time.format("%Y-%m-%d %H:00").to_string(),
+            TimeResolution::Daily => time.format("%Y-%m-%d").to_string(),
+            TimeResolution::Weekly => time.format("%G-W%V").to_string(),
+            TimeResolution::Monthly => time.format("%Y-%m").to_string(),
+            TimeResolution::Yearly => time.format("%Y").to_string(),
+            TimeResolution::HourOfDay => time.format("%H").to_string(),
+        }
+    }
+
+    /// Returns all labels in order if the resolution folds time into a recurring cycle (like the hours of a day).
+    pub fn cyclic_labels(&self) -> Option<Vec<String>> {
+        match self {
+            TimeResolution::HourOfDay => Some((0..24).map(|hour| format!("{:02}", hour)).collect()),
+            _ => None,
+        }
+    }
+
+    /// Returns the start of the time bucket with the given label, if it is a label of this resolution.
+    ///
+    /// Buckets of cyclic resolutions recur, so they have no start time.
+    pub fn start_time_of(&self, label: &str) -> Option<NaiveDateTime> {
+        let start_date = match self {
+            TimeResolution::Hourly => {
+                return NaiveDateTime::parse_from_str(label, "%Y-%m-%d %H:%M").ok();
+            }
+            TimeResolution::Daily => NaiveDate::parse_from_str(label, "%Y-%m-%d"),
+            TimeResolution::Weekly => NaiveDate::parse_from_str(&format!("{}-1", label), "%G-W%V-%u"),
+            TimeResolution::Monthly => NaiveDate::parse_from_str(&format!("{}-01", label), "%Y-%m-%d"),
+            TimeResolution::Yearly => NaiveDate::parse_from_str(&format!("{}-01-01", label), "%Y-%m-%d"),
+            TimeResolution::HourOfDay => return None,
+        };
+        start_date.ok().map(|date| date.and_time(NaiveTime::MIN))
+    }
+
+    /// Returns the label of the time bucket after the one with the given label, if it is a label of this resolution.
+    pub fn next_label(&self, label: &str) -> Option<String> {
+        let start = self.start_time_of(label)?;
+        let next = match self {
+            TimeResolution::Hourly => start.checked_add_signed(TimeDelta::hours(1)),
+            TimeResolution::Daily => start.checked_add_days(Days::new(1)),
+            TimeResolution::Weekly => start.checked_add_days(Days::new(7)),
+            TimeResolution::Monthly => start.checked_add_months(Months::new(1)),
+            TimeResolution::Yearly => start.checked_add_months(Months::new(12)),
+            TimeResolution::HourOfDay => None,
+        }?;
+        Some(self.label(&next.and_utc()))
+    }
+}
+
+/// Parses a date like `2025-01-31`.
+fn parse_date(date: &str) -> Result<NaiveDate, Box<dyn Error>> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}', expected a date like '2025-01-31'.", date).into())
+}
+
+/// Parses a time zone given by its IANA name, e.g. `Europe/Berlin`.
+pub fn parse_time_zone(timezone: &str) -> Result<Tz, Box<dyn Error>> {
+    timezone.parse::<Tz>().map_err(|_| {
+        format!("Unknown time zone '{}', expected an IANA name like 'America/Los_Angeles'.", timezone).into()
+    })
+}
+
+/// How the channel and message patterns of a task are matched.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub enum MatchMode {
+    /// The text needs to contain the pattern.
+    #[default]
+    Substring,
+    /// The text needs to contain the pattern, ignoring case.
+    CaseInsensitiveSubstring,
+    /// The text needs to contain a match of the pattern as a regular expression (e.g. `bug|issue|ticket`).
+    Regex,
+}
+
+/// The unit the length of a message is measured in.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum LengthUnit {
+    #[default]
+    Characters,
+    /// Words, ignoring Slack markup and emoji shortcodes (see `text::tokenize`).
+    Words,
+}
+
+/// The image format of a plot.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutputFormat {
+    Png,
+    Svg,
+    /// An interactive chart (with tooltips) in an HTML page, which loads Vega-Lite from a CDN.
+    /// Only supported for plots of a single count or value series, and for ratio plots.
+    Html,
+}
+
+/// How to treat ratio buckets that are suppressed because their denominator is too small.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub enum SuppressionMode {
+    /// Leave out the bucket.
+    #[default]
+    Omit,
+    /// Show a ratio of zero for the bucket.
+    Zero,
+}
+
+/// An event to annotate in plots, e.g. a release or an incident.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Event {
+    /// The date of the event, in the format `YYYY-MM-DD`.
+    pub date: String,
+    pub label: String,
+}
+
+/// Styling defaults shared by all tasks, e.g. a corporate theme.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct Theme {
+    /// Colors by name, which tasks (and the theme itself) can use instead of hex strings.
+    pub named_colors: Option<BTreeMap<String, String>>,
+    /// Default colors for tasks that define none.
+    pub colors: Option<Colors>,
+    /// Default background color for tasks that define none.
+    pub background: Option<String>,
+    /// Default font family for tasks that define none.
+    pub font: Option<String>,
+    /// Default for showing grid lines, for tasks that do not define it.
+    pub grid: Option<bool>,
+}
+impl Theme {
+    /// Returns the hex string of a named color, or the color itself if it has no name in this theme.
+    fn resolve_color(&self, color: &str) -> String {
+        self.named_colors
+            .as_ref()
+            .and_then(|named_colors| named_colors.get(color))
+            .cloned()
+            .unwrap_or_else(|| color.to_string())
+    }
 }
 
+/// Custom plot colors (as hex strings), either by position of the series or by series name (e.g. a channel).
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(untagged)]
+pub enum Colors {
+    List(Vec<String>),
+    ByName(BTreeMap<String, String>),
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone)]
 pub struct PlotTask {
     pub metric: Metric,
     pub resolution: TimeResolution,
     pub output_file_name: String,
-    pub colors: Option<Vec<String>>,
+    pub colors: Option<Colors>,
+    /// If set, count series are plotted as z-scores relative to this many preceding buckets.
+    pub zscore_window: Option<usize>,
+    /// If true, deleted messages (tombstones) are counted as well.
+    pub include_deleted: Option<bool>,
+    /// If false, replies in threads are ignored, so that only top-level messages are counted (defaults to true).
+    pub include_thread_replies: Option<bool>,
+    /// If true, messages posted by bots (with a bot ID or the `bot_message` subtype) are ignored by all metrics.
+    pub exclude_bots: Option<bool>,
+    /// If set, only messages from users whose ID or display name matches this pattern are considered by all metrics.
+    pub user_pattern: Option<String>,
+    /// If set, messages with fewer characters than this are ignored.
+    pub min_message_length: Option<usize>,
+    /// If set, message times are rounded to the nearest multiple of this many minutes before bucketing.
+    pub time_round_minutes: Option<u32>,
+    /// Scale factor for image dimensions and font sizes (defaults to 1.0).
+    pub scale: Option<f64>,
+    /// Events to annotate as vertical lines, if they fall into the plotted time range.
+    pub events: Option<Vec<Event>>,
+    /// If set, ratio buckets where both counts together are below this are suppressed.
+    pub min_denominator: Option<usize>,
+    /// How to suppress ratio buckets below `min_denominator` (defaults to `Omit`).
+    pub min_denominator_mode: Option<SuppressionMode>,
+    /// If set, ratio plots additionally show the centered moving average over this many buckets.
+    pub smoothing_window: Option<usize>,
+    /// Number of empty positions to add on either side of the x-axis of line charts (defaults to 0).
+    pub x_padding: Option<usize>,
+    /// Pairs of thresholds and hex colors: histogram bars reaching a threshold get its color
+    /// (the one of the highest threshold reached); bars below all thresholds keep the default color.
+    pub color_thresholds: Option<Vec<(f64, String)>>,
+    /// If true, the precise timestamps of all messages matching a message pattern are written to a text file.
+    pub emit_timestamps: Option<bool>,
+    /// If set, time bucket labels are displayed in this (strftime) format, e.g. `%b %Y` for 'Jan 2024'.
+    /// Grouping, sorting, and CSV files still use the sortable labels.
+    pub label_format: Option<String>,
+    /// If true, metrics averaging per-thread values also show error bars of one standard deviation.
+    pub show_error_bars: Option<bool>,
+    /// Background color of the plots as a hex string (defaults to white).
+    pub background: Option<String>,
+    /// Font family of all texts in the plots (defaults to `sans-serif`).
+    pub font: Option<String>,
+    /// If false, charts are drawn without grid lines (defaults to true).
+    pub grid: Option<bool>,
+    /// If true, message counts are divided by the number of members of the matching channels (from `channels.json`).
+    pub per_member: Option<bool>,
+    /// If true, the counts of `MentionCount` tasks are split by channel and plotted as stacked histogram.
+    pub stack_by_channel: Option<bool>,
+    /// How channel and message patterns are matched (defaults to `Substring`).
+    pub match_mode: Option<MatchMode>,
+    /// IANA name of the time zone that messages are bucketed in, e.g. `America/Los_Angeles` (defaults to UTC).
+    pub timezone: Option<String>,
+    /// Message subtypes that are not counted (defaults to `slack::DEFAULT_EXCLUDED_SUBTYPES`, i.e. system events,
+    /// bot messages, edits, and deletions); e.g. set to `[]` to count bot messages as well.
+    pub excluded_subtypes: Option<Vec<String>>,
+    /// If true, histograms at `Daily` resolution shade the background of Saturdays and Sundays.
+    pub weekend_shading: Option<bool>,
+    /// If true, charts are drawn edge-to-edge without caption, axes, labels, legend, or events (e.g. as sparklines).
+    pub minimal: Option<bool>,
+    /// Image format of the plot (defaults to the one matching the extension of `output_file_name`, PNG otherwise).
+    pub output_format: Option<OutputFormat>,
+    /// If true, messages in archived channels (according to `channels.json`) are ignored.
+    pub exclude_archived: Option<bool>,
+    /// If set (e.g. to `2024-01-01`), only messages in channels created after this date (according to `channels.json`)
+    /// are considered.
+    pub created_after: Option<String>,
+    /// The number of members per channel name, if known (not configurable in task files).
+    #[serde(skip)]
+    pub channel_members: Option<BTreeMap<String, usize>>,
+    /// The names of the channels excluded by `exclude_archived` and `created_after` (not configurable in task files).
+    #[serde(skip)]
+    pub excluded_channels: BTreeSet<String>,
+    /// If true, series are only computed, without writing any CSV or image files (not configurable in task files).
+    #[serde(skip)]
+    pub skip_output: bool,
+    /// If true, only CSV files are written, without rendering any images (not configurable in task files).
+    #[serde(skip)]
+    pub skip_image: bool,
 }
 impl PlotTask {
+    #[cfg(test)]
+    pub(crate) fn new(metric: Metric, resolution: TimeResolution, output_file_name: &str) -> PlotTask {
+        PlotTask {
+            metric,
+            resolution,
+            output_file_name: output_file_name.into(),
+            colors: Option::None,
+            zscore_window: Option::None,
+            include_deleted: Option::None,
+            include_thread_replies: Option::None,
+            exclude_bots: Option::None,
+            user_pattern: Option::None,
+            min_message_length: Option::None,
+            time_round_minutes: Option::None,
+            scale: Option::None,
+            events: Option::None,
+            min_denominator: Option::None,
+            min_denominator_mode: Option::None,
+            smoothing_window: Option::None,
+            x_padding: Option::None,
+            color_thresholds: Option::None,
+            emit_timestamps: Option::None,
+            label_format: Option::None,
+            show_error_bars: Option::None,
+            background: Option::None,
+            font: Option::None,
+            grid: Option::None,
+            per_member: Option::None,
+            stack_by_channel: Option::None,
+            match_mode: Option::None,
+            timezone: Option::None,
+            excluded_subtypes: Option::None,
+            weekend_shading: Option::None,
+            minimal: Option::None,
+            output_format: Option::None,
+            exclude_archived: Option::None,
+            created_after: Option::None,
+            channel_members: Option::None,
+            excluded_channels: BTreeSet::new(),
+            skip_output: false,
+            skip_image: false,
+        }
+    }
+
     fn rgb_from_hex(hex_str: &str) -> Result<RGBColor, Box<dyn Error>> {
         let hex = hex_str.trim_start_matches('#');
         if hex.len() != 6 {
@@ -44,14 +730,271 @@ impl PlotTask {
         Ok(RGBColor(r, g, b))
     }
     pub fn custom_color(&self, index: usize) -> RGBColor {
-        if let Some(colors) = &self.colors {
-            if index < colors.len() {
-                return PlotTask::rgb_from_hex(&colors[index]).unwrap_or(BLUE);
-            }
+        self.custom_color_or(index, BLUE)
+    }
+    pub fn custom_color_or(&self, index: usize, default: RGBColor) -> RGBColor {
+        if let Some(Colors::List(colors)) = &self.colors
+            && index < colors.len()
+        {
+            return PlotTask::rgb_from_hex(&colors[index]).unwrap_or(default);
+        }
+        default
+    }
+    /// The color of the series with the given index and name: mapped by name if colors are given as a map,
+    /// otherwise by index, falling back to the default series colors.
+    pub fn series_color(&self, index: usize, name: &str) -> RGBColor {
+        let default = default_series_color(index);
+        match &self.colors {
+            Some(Colors::ByName(colors)) => colors
+                .get(name)
+                .and_then(|hex| PlotTask::rgb_from_hex(hex).ok())
+                .unwrap_or(default),
+            _ => self.custom_color_or(index, default),
+        }
+    }
+    /// Returns the color of the highest threshold in `color_thresholds` that the value reaches, if any.
+    pub fn threshold_color(&self, value: f64) -> Option<RGBColor> {
+        self.color_thresholds
+            .iter()
+            .flatten()
+            .filter(|(threshold, _)| value >= *threshold)
+            .max_by(|(threshold1, _), (threshold2, _)| threshold1.total_cmp(threshold2))
+            .and_then(|(_, hex)| PlotTask::rgb_from_hex(hex).ok())
+    }
+    /// Returns the image dimensions, scaled by the task's `scale` factor.
+    pub fn image_dim(&self) -> (u32, u32) {
+        (self.scaled(DEFAULT_IMAGE_DIM.0), self.scaled(DEFAULT_IMAGE_DIM.1))
+    }
+    /// Scales a size in pixels (of fonts, margins, etc.) by the task's `scale` factor.
+    pub fn scaled(&self, size: u32) -> u32 {
+        (size as f64 * self.scale.unwrap_or(1.0)).round() as u32
+    }
+    /// Returns a copy of the task that only computes its series, without writing any files.
+    pub fn without_output(&self) -> PlotTask {
+        PlotTask {
+            skip_output: true,
+            ..self.clone()
+        }
+    }
+    /// Returns a copy of the task that writes its CSV files, but renders no images.
+    pub fn without_image(&self) -> PlotTask {
+        PlotTask {
+            skip_image: true,
+            ..self.clone()
+        }
+    }
+    /// Returns a copy of the task with the given events, unless the task defines its own events.
+    pub fn with_events(&self, events: &[Event]) -> PlotTask {
+        PlotTask {
+            events: self.events.clone().or_else(|| Some(events.to_vec())),
+            ..self.clone()
+        }
+    }
+    /// Returns the indices of the given labels at which the task's events happened, together with the event labels.
+    ///
+    /// Events outside the labels' range (or with invalid dates) are ignored, as are all events at cyclic resolutions.
+    pub fn event_positions(&self, labels: &[String]) -> Vec<(usize, String)> {
+        if self.resolution.cyclic_labels().is_some() {
+            return Vec::new();
+        }
+        self.events
+            .iter()
+            .flatten()
+            .filter_map(|event| match NaiveDate::parse_from_str(&event.date, "%Y-%m-%d") {
+                Ok(date) => {
+                    let time_label = self.resolution.label(&date.and_time(NaiveTime::MIN).and_utc());
+                    labels
+                        .iter()
+                        .position(|label| *label == time_label)
+                        .map(|index| (index, event.label.clone()))
+                }
+                Err(err) => {
+                    eprintln!("Ignoring event '{}' with invalid date: {}", event.label, err);
+                    None
+                }
+            })
+            .collect()
+    }
+    /// Returns the indices of the labels that are weekend days (Saturday or Sunday), if weekend shading is enabled.
+    ///
+    /// Weekends are only shaded at `Daily` resolution, as other buckets cover weekdays and weekends alike.
+    pub fn weekend_positions(&self, labels: &[String]) -> Vec<usize> {
+        if !self.weekend_shading.unwrap_or(false) || self.resolution != TimeResolution::Daily {
+            return Vec::new();
+        }
+        labels
+            .iter()
+            .enumerate()
+            .filter(|(_, label)| {
+                self.resolution
+                    .start_time_of(label)
+                    .is_some_and(|time| matches!(time.weekday(), Weekday::Sat | Weekday::Sun))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+    /// Returns the label to display for a time bucket, formatted with the task's `label_format` if set.
+    ///
+    /// Labels that are no time buckets of the task's resolution (e.g. hours or channels) are displayed as they are.
+    pub fn display_label(&self, label: &str) -> String {
+        match (&self.label_format, self.resolution.start_time_of(label)) {
+            (Some(label_format), Some(time)) => time.format(label_format).to_string(),
+            _ => label.to_string(),
+        }
+    }
+    /// Returns a copy of the task with the theme's defaults for all styling options the task does not define,
+    /// and with the theme's named colors replaced by their hex strings.
+    pub fn with_theme(&self, theme: &Theme) -> PlotTask {
+        let colors = match self.colors.as_ref().or(theme.colors.as_ref()) {
+            Some(Colors::List(colors)) => Some(Colors::List(
+                colors.iter().map(|color| theme.resolve_color(color)).collect(),
+            )),
+            Some(Colors::ByName(colors)) => Some(Colors::ByName(
+                colors
+                    .iter()
+                    .map(|(name, color)| (name.clone(), theme.resolve_color(color)))
+                    .collect(),
+            )),
+            None => None,
+        };
+        PlotTask {
+            colors,
+            background: self
+                .background
+                .as_ref()
+                .or(theme.background.as_ref())
+                .map(|color| theme.resolve_color(color)),
+            font: self.font.clone().or_else(|| theme.font.clone()),
+            grid: self.grid.or(theme.grid),
+            ..self.clone()
+        }
+    }
+    /// Returns the centered moving average of the series over `smoothing_window` buckets, if one is set.
+    pub fn smoothed(&self, time_series: &[(String, f64)]) -> Option<Vec<(String, f64)>> {
+        let window = self.smoothing_window.filter(|window| *window > 1)?;
+        let values: Vec<f64> = time_series.iter().map(|(_, value)| *value).collect();
+        Some(
+            time_series
+                .iter()
+                .zip(stats::centered_moving_average(&values, window))
+                .map(|((label, _), average)| (label.clone(), average))
+                .collect(),
+        )
+    }
+    /// Returns the message subtypes that are not counted; deleted messages are counted if `include_deleted` is set.
+    pub fn excluded_subtypes(&self) -> Vec<&str> {
+        let excluded_subtypes: Vec<&str> = match &self.excluded_subtypes {
+            Some(subtypes) => subtypes.iter().map(String::as_str).collect(),
+            None => slack::DEFAULT_EXCLUDED_SUBTYPES.to_vec(),
+        };
+        let include_deleted = self.include_deleted.unwrap_or(false);
+        excluded_subtypes
+            .into_iter()
+            .filter(|subtype| !(include_deleted && *subtype == "tombstone"))
+            .collect()
+    }
+    /// Compiles the given pattern according to the task's match mode.
+    pub fn matcher(&self, pattern: &str) -> Result<Matcher, regex::Error> {
+        Ok(match self.match_mode.clone().unwrap_or_default() {
+            MatchMode::Substring => Matcher::Substring(pattern.to_string()),
+            MatchMode::CaseInsensitiveSubstring => Matcher::CaseInsensitiveSubstring(pattern.to_lowercase()),
+            MatchMode::Regex => Matcher::Regex(Regex::new(pattern)?),
+        })
+    }
+    /// Returns the time zone that messages are bucketed in.
+    pub fn time_zone(&self) -> Result<Tz, Box<dyn Error>> {
+        self.timezone.as_deref().map_or(Ok(Tz::UTC), parse_time_zone)
+    }
+    /// Returns a copy of the task with the given time zone, unless it has one already.
+    pub fn with_timezone(&self, timezone: &str) -> PlotTask {
+        PlotTask {
+            timezone: self.timezone.clone().or_else(|| Some(timezone.to_string())),
+            ..self.clone()
+        }
+    }
+    /// Checks the task's metric, time zone, and dates, and that all its patterns can be compiled.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        self.metric.validate()?;
+        if self.stack_by_channel.unwrap_or(false)
+            && (!matches!(self.metric, Metric::MentionCount { .. }) || self.per_member.unwrap_or(false))
+        {
+            return Err(format!(
+                "Task '{}' can only be stacked by channel for MentionCount metrics, and not per member.",
+                self.output_file_name
+            )
+            .into());
+        }
+        if let Some(date) = &self.created_after {
+            parse_date(date)?;
+        }
+        if matches!(self.metric, Metric::CumulativeCount { .. } | Metric::MembershipGrowth { .. })
+            && self.resolution.cyclic_labels().is_some()
+        {
+            return Err(format!("Task '{}' accumulates counts over time, so its resolution cannot be cyclic.", self.output_file_name).into());
+        }
+        if matches!(self.metric, Metric::ThreadReplyCount { .. }) && !self.include_thread_replies.unwrap_or(true) {
+            return Err(format!("Task '{}' counts thread replies, so it needs to include them.", self.output_file_name).into());
+        }
+        self.time_zone()?;
+        for pattern in self.metric.patterns().into_iter().chain(self.user_pattern.as_deref()) {
+            self.matcher(pattern).map_err(|error| {
+                format!("Invalid pattern '{}' in task '{}': {}", pattern, self.output_file_name, error)
+            })?;
+        }
+        Ok(())
+    }
+    /// Returns a copy of the task with the given numbers of members per channel name.
+    pub fn with_channel_members(&self, channel_members: &BTreeMap<String, usize>) -> PlotTask {
+        PlotTask {
+            channel_members: Some(channel_members.clone()),
+            ..self.clone()
         }
-        BLUE
     }
-    pub fn with_output_dir(&self, output_dir: &PathBuf) -> PlotTask {
+    /// Returns true if the task filters channels by their metadata, which needs to be set via `with_channels`.
+    pub fn filters_channels(&self) -> bool {
+        self.exclude_archived.unwrap_or(false) || self.created_after.is_some()
+    }
+    /// Returns a copy of the task that excludes the given channels according to `exclude_archived` and `created_after`.
+    /// Channels without a creation time are kept.
+    pub fn with_channels(&self, channels: &[slack::Channel]) -> PlotTask {
+        // The date is checked when validating the task
+        let created_after = self.created_after.as_deref().and_then(|date| parse_date(date).ok());
+        let excluded_channels = channels
+            .iter()
+            .filter(|channel| {
+                (self.exclude_archived.unwrap_or(false) && channel.is_archived())
+                    || created_after.is_some_and(|date| {
+                        channel.created_time().is_some_and(|created| created.date_naive() <= date)
+                    })
+            })
+            .map(|channel| channel.name.clone())
+            .collect();
+        PlotTask {
+            excluded_channels,
+            ..self.clone()
+        }
+    }
+    pub fn background_color(&self) -> RGBColor {
+        self.background
+            .as_ref()
+            .and_then(|hex| PlotTask::rgb_from_hex(hex).ok())
+            .unwrap_or(WHITE)
+    }
+    pub fn font_family(&self) -> &str {
+        self.font.as_deref().unwrap_or("sans-serif")
+    }
+    /// The image format to plot in, either configured explicitly or inferred from the output file name.
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format.unwrap_or_else(|| {
+            match Path::new(&self.output_file_name).extension().and_then(|ext| ext.to_str()) {
+                Some(ext) if ext.eq_ignore_ascii_case("svg") => OutputFormat::Svg,
+                Some(ext) if ext.eq_ignore_ascii_case("html") => OutputFormat::Html,
+                _ => OutputFormat::Png,
+            }
+        })
+    }
+
+    pub fn with_output_dir(&self, output_dir: &Path) -> PlotTask {
         PlotTask {
             output_file_name: output_dir.join(&self.output_file_name).to_str().unwrap().to_string(),
             ..self.clone()
@@ -59,46 +1002,76 @@ impl PlotTask {
     }
 }
 
-pub fn read_tasks_from_file(file_path: &str, output_dir: &PathBuf) -> Result<Vec<PlotTask>, Box<dyn Error>> {
+pub fn read_tasks_from_file(file_path: &str, output_dir: &Path) -> Result<Vec<PlotTask>, Box<dyn Error>> {
     let file_content = fs::read_to_string(file_path)?;
     let tasks: Vec<PlotTask> = serde_json::from_str(&file_content)?;
+    for task in &tasks {
+        task.validate()?;
+    }
     let tasks_with_output_dir: Vec<PlotTask> = tasks.iter().map(|task| {
         task.with_output_dir(output_dir)
     }).collect();
     Ok(tasks_with_output_dir)
 }
 
-fn calculate_max_y_axis(message_counts: &Vec<(String, usize)>) -> usize {
-    (message_counts.iter().map(|x| x.1).max().unwrap_or(0) as f64 * 1.1) as usize
+/// Reads a theme (a JSON object with named colors and styling defaults) to apply to all tasks.
+pub fn read_theme_from_file(file_path: &Path) -> Result<Theme, Box<dyn Error>> {
+    let file_content = fs::read_to_string(file_path)?;
+    Ok(serde_json::from_str(&file_content)?)
+}
+
+/// Reads events (a JSON list of objects with `date` and `label`) to annotate in plots.
+pub fn read_events_from_file(file_path: &Path) -> Result<Vec<Event>, Box<dyn Error>> {
+    let file_content = fs::read_to_string(file_path)?;
+    Ok(serde_json::from_str(&file_content)?)
+}
+
+fn calculate_max_y_axis(message_counts: &[(String, usize)]) -> usize {
+    // At least 1, so that the y-axis does not collapse if all counts are zero
+    ((message_counts.iter().map(|x| x.1).max().unwrap_or(0) as f64 * 1.1) as usize).max(1)
 }
 
-fn calculate_time_series_ratios(
-    labels: &Vec<String>,
-    message_counts1: &Vec<(String, usize)>,
-    message_counts2: &Vec<(String, usize)>,
+/// Calculates the ratio `count1 / (count1 + count2)` per label.
+///
+/// Buckets with a denominator below the task's `min_denominator` are omitted or zeroed, depending on
+/// the task's `min_denominator_mode`.
+pub(crate) fn calculate_time_series_ratios(
+    task: &PlotTask,
+    labels: &[String],
+    message_counts1: &[(String, usize)],
+    message_counts2: &[(String, usize)],
 ) -> Vec<(String, f64)> {
+    let min_denominator = task.min_denominator.unwrap_or(0);
+    let mode = task.min_denominator_mode.clone().unwrap_or_default();
     labels
         .iter()
         .zip(message_counts1.iter())
         .zip(message_counts2.iter())
-        .map(|((label, (_, count1)), (_, count2))| {
-            let ratio = if *count2 + *count1 == 0 {
+        .filter_map(|((label, (_, count1)), (_, count2))| {
+            let denominator = *count1 + *count2;
+            let ratio = if denominator == 0 {
                 0.0
+            } else if denominator < min_denominator {
+                match mode {
+                    SuppressionMode::Omit => return None,
+                    SuppressionMode::Zero => 0.0,
+                }
             } else {
-                *count1 as f64 / (*count1 + *count2) as f64
+                *count1 as f64 / denominator as f64
             };
-            (label.clone(), ratio)
+            Some((label.clone(), ratio))
         })
         .collect()
 }
 
-fn label_set(message_counts: &Vec<(String, usize)>) -> HashSet<String> {
+fn label_set(message_counts: &[(String, usize)]) -> HashSet<String> {
     message_counts
         .iter()
-        .map(|(label, _count)| (label.clone()))
+        .map(|(label, _count)| label.clone())
         .collect()
 }
 
+#[allow(clippy::type_complexity)]
 fn consolidate_labels(
     message_counts1: Vec<(String, usize)>,
     message_counts2: Vec<(String, usize)>,
@@ -117,11 +1090,47 @@ fn consolidate_labels(
     (filtered_message_counts1, filtered_message_counts2)
 }
 
-fn write_message_counts_to_csv(
+/// Aligns several count series on the union of their labels (in order), counting missing labels as zero.
+///
+/// Unlike `consolidate_labels`, which keeps only the shared labels as ratios need both counts,
+/// no bucket of any series is dropped.
+fn align_labels(named_counts: &[(String, Vec<(String, usize)>)]) -> Vec<(String, Vec<(String, usize)>)> {
+    let labels: BTreeSet<&String> = named_counts
+        .iter()
+        .flat_map(|(_, counts)| counts.iter().map(|(label, _)| label))
+        .collect();
+    named_counts
+        .iter()
+        .map(|(name, counts)| {
+            let counts_by_label: HashMap<&String, usize> =
+                counts.iter().map(|(label, count)| (label, *count)).collect();
+            let aligned_counts = labels
+                .iter()
+                .map(|label| ((*label).clone(), counts_by_label.get(label).copied().unwrap_or(0)))
+                .collect();
+            (name.clone(), aligned_counts)
+        })
+        .collect()
+}
+
+fn write_message_counts_to_csv<T: Serialize>(
+    description: Option<&str>,
+    task: &PlotTask,
+    message_counts: &[(String, T)],
+) -> Result<(), Box<dyn Error>> {
+    write_message_counts_to_sink(&FileSink, description, task, message_counts)
+}
+
+fn write_message_counts_to_sink<T: Serialize>(
+    sink: &dyn ArtifactSink,
     description: Option<&str>,
-    output_file_name: &str,
-    message_counts: &Vec<(String, usize)>,
+    task: &PlotTask,
+    message_counts: &[(String, T)],
 ) -> Result<(), Box<dyn Error>> {
+    if task.skip_output {
+        return Ok(());
+    }
+    let output_file_name = &task.output_file_name;
     let csv_output_file_name: String = description.map_or_else(
         || String::from(output_file_name) + ".csv",
         |desc| String::from(output_file_name) + "-" + desc + ".csv",
@@ -133,162 +1142,1479 @@ fn write_message_counts_to_csv(
         &csv_output_file_name
     ));
 
-    let mut wtr = Writer::from_path(csv_output_file_name)?;
+    let mut wtr = Writer::from_writer(Vec::new());
     for (name, count) in message_counts.iter() {
         wtr.serialize((name, count))?;
     }
-    wtr.flush()?;
-    Ok(())
+    sink.write(&csv_output_file_name, &wtr.into_inner()?)
 }
 
 pub fn counter_plot(
     task: &PlotTask,
-    message_pattern: &str,
-    message_counts: &Vec<(String, usize)>,
+    description: &str,
+    message_counts: &[(String, usize)],
+) -> Result<(), Box<dyn Error>> {
+    counter_plot_to(&FileSink, task, description, message_counts)
+}
+
+/// Plots the counts like `counter_plot`, but writes the image and CSV file to the given sink.
+pub fn counter_plot_to(
+    sink: &dyn ArtifactSink,
+    task: &PlotTask,
+    description: &str,
+    message_counts: &[(String, usize)],
 ) -> Result<(), Box<dyn Error>> {
     println!(
-        "Plotting {} messages mentioning '{}' to '{}'.",
+        "Plotting {} counts of {} to '{}'.",
         message_counts.len(),
-        message_pattern,
+        description,
         task.output_file_name
     );
-    let max_y_axis: usize = calculate_max_y_axis(message_counts);
-    let labels: Vec<String> = message_counts
-        .iter()
-        .map(|(time_label, _)| time_label.clone())
-        .collect();
+    write_message_counts_to_sink(sink, Option::None, task, message_counts)?;
+    let html_chart = || HtmlChart {
+        caption: format!("Slack {} over time", description),
+        mark: "bar",
+        named_series: vec![(
+            description.to_string(),
+            message_counts.iter().map(|(label, count)| (label.clone(), *count as f64)).collect(),
+        )],
+    };
+    render_chart_to_sink(sink, task, html_chart, |root| {
+        draw_counter_histogram(root, task, description, message_counts)
+    })
+}
 
-    write_message_counts_to_csv(Option::None, &task.output_file_name, message_counts)?;
+/// Renders the same histogram as `counter_plot` into PNG bytes, without writing any files.
+pub fn counter_plot_png(
+    task: &PlotTask,
+    description: &str,
+    message_counts: &[(String, usize)],
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    render_to_png(task, |root| {
+        draw_counter_histogram(root, task, description, message_counts)
+    })
+}
 
-    let root = BitMapBackend::new(&task.output_file_name, DEFAULT_IMAGE_DIM).into_drawing_area();
-    root.fill(&WHITE)?;
-    let mut chart = ChartBuilder::on(&root)
-        .margin(20)
-        .caption(
-            format!("Slack messages mentioning '{}' over time", message_pattern),
-            ("sans-serif", 30).into_font(),
-        )
-        .x_label_area_size(30)
-        .y_label_area_size(30)
-        .build_cartesian_2d(labels.into_segmented(), 0..max_y_axis)?;
+fn draw_counter_histogram<DB>(
+    root: &DrawingArea<DB, Shift>,
+    task: &PlotTask,
+    description: &str,
+    message_counts: &[(String, usize)],
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    draw_histogram(
+        root,
+        task,
+        &format!("Slack {} over time", description),
+        message_counts,
+        &vec![false; message_counts.len()],
+    )
+}
 
-    chart
-        .configure_mesh()
-        .x_label_style(("sans-serif", 25).into_text_style(&root))
-        .y_label_style(("sans-serif", 25).into_text_style(&root))
-        .draw()?;
+/// Plots a count series as histogram, highlighting some buckets (e.g. bursts) in the second custom color (red by default).
+///
+/// The highlighted buckets are additionally written to a separate CSV file, named after `highlight_name`.
+pub fn highlighted_plot(
+    task: &PlotTask,
+    description: &str,
+    message_counts: &[(String, usize)],
+    highlighted: &[bool],
+    highlight_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let highlighted_counts: Vec<(String, usize)> = message_counts
+        .iter()
+        .zip(highlighted.iter())
+        .filter(|(_, is_highlighted)| **is_highlighted)
+        .map(|(count, _)| count.clone())
+        .collect();
+    println!(
+        "Plotting {} counts of {} with {} {} to '{}'.",
+        message_counts.len(),
+        description,
+        highlighted_counts.len(),
+        highlight_name,
+        task.output_file_name
+    );
+    write_message_counts_to_csv(Option::None, task, message_counts)?;
+    write_message_counts_to_csv(
+        Option::Some(highlight_name),
+        task,
+        &highlighted_counts,
+    )?;
+    render_to_file(task, |root| {
+        draw_histogram(
+            root,
+            task,
+            &format!("Slack {} ({} highlighted)", description, highlight_name),
+            message_counts,
+            highlighted,
+        )
+    })
+}
 
-    chart
-        .draw_series(
+fn draw_histogram<DB>(
+    root: &DrawingArea<DB, Shift>,
+    task: &PlotTask,
+    caption: &str,
+    message_counts: &[(String, usize)],
+    highlighted: &[bool],
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    if message_counts.is_empty() {
+        return Err(no_data_error(task));
+    }
+    let max_y_axis: usize = calculate_max_y_axis(message_counts);
+    let time_labels: Vec<String> = message_counts
+        .iter()
+        .map(|(time_label, _)| time_label.clone())
+        .collect();
+    let labels: Vec<String> = time_labels
+        .iter()
+        .map(|time_label| task.display_label(time_label))
+        .collect();
+
+    let x_label_count = calculate_x_label_count(task, &labels);
+    let minimal = task.minimal.unwrap_or(false);
+
+    root.fill(&task.background_color())?;
+    let mut chart = chart_builder(root, task, caption, task.scaled(20))
+        .build_cartesian_2d(labels.into_segmented(), 0..max_y_axis)?;
+
+    if !minimal {
+        let mut mesh = chart.configure_mesh();
+        mesh.x_labels(x_label_count)
+            .x_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .y_label_style((task.font_family(), task.scaled(25)).into_text_style(root));
+        if !task.grid.unwrap_or(true) {
+            mesh.disable_mesh();
+        }
+        mesh.draw()?;
+    }
+
+    for index in task.weekend_positions(&time_labels) {
+        let end = labels.get(index + 1).map_or(SegmentValue::Last, SegmentValue::Exact);
+        chart.draw_series(std::iter::once(Rectangle::new(
+            [(SegmentValue::Exact(&labels[index]), 0), (end, max_y_axis)],
+            BLACK.mix(0.06).filled(),
+        )))?;
+    }
+
+    let bar_colors: Vec<RGBColor> = message_counts
+        .iter()
+        .zip(highlighted.iter())
+        .map(|((_, count), is_highlighted)| {
+            if *is_highlighted {
+                task.custom_color_or(1, RED)
+            } else {
+                task.threshold_color(*count as f64)
+                    .unwrap_or_else(|| task.custom_color(0))
+            }
+        })
+        .collect();
+    let mut distinct_colors: Vec<RGBColor> = Vec::new();
+    for color in &bar_colors {
+        if !distinct_colors.contains(color) {
+            distinct_colors.push(*color);
+        }
+    }
+    for color in distinct_colors {
+        chart.draw_series(
             Histogram::vertical(&chart)
-                .margin(calculate_margin(0.2, labels.len()))
-                .style(task.custom_color(0).filled())
+                .margin(calculate_margin(0.2, labels.len(), task.image_dim().0))
+                .style(color.filled())
                 .data(
                     labels
                         .iter()
                         .zip(message_counts.iter())
-                        .map(|(label, (_, count))| (label, *count)),
+                        .zip(bar_colors.iter())
+                        .filter(|(_, bar_color)| **bar_color == color)
+                        .map(|((label, (_, count)), _)| (label, *count)),
                 ),
+        )?;
+    }
+    if minimal {
+        return Ok(());
+    }
+    for (index, event_label) in task.event_positions(&time_labels) {
+        let x = SegmentValue::CenterOf(&labels[index]);
+        draw_event(&mut chart, task, event_label, (x.clone(), 0), (x, max_y_axis))?;
+    }
+    Ok(())
+}
+
+/// Plots several named count series as grouped histogram, aligned on the union of their labels.
+///
+/// Each series is drawn in the task's custom color of its index, and listed in a legend.
+/// The CSV file contains one column per series.
+pub fn multi_counter_plot(
+    task: &PlotTask,
+    description: &str,
+    named_counts: &[(String, Vec<(String, usize)>)],
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "Plotting {} count series of {} to '{}'.",
+        named_counts.len(),
+        description,
+        task.output_file_name
+    );
+    let named_counts = align_labels(named_counts);
+    let named_series: Vec<(String, Vec<(String, f64)>)> = named_counts
+        .iter()
+        .map(|(name, counts)| {
+            let values = counts.iter().map(|(label, count)| (label.clone(), *count as f64)).collect();
+            (name.clone(), values)
+        })
+        .collect();
+    write_series_to_csv(task, &named_series)?;
+    render_to_file(task, |root| {
+        draw_grouped_histogram(root, task, &format!("Slack {} over time", description), &named_counts)
+    })
+}
+
+fn draw_grouped_histogram<DB>(
+    root: &DrawingArea<DB, Shift>,
+    task: &PlotTask,
+    caption: &str,
+    named_counts: &[(String, Vec<(String, usize)>)],
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    let time_labels: Vec<String> = named_counts
+        .first()
+        .map(|(_, counts)| counts.iter().map(|(label, _)| label.clone()).collect())
+        .unwrap_or_default();
+    if time_labels.is_empty() {
+        return Err(no_data_error(task));
+    }
+    let labels: Vec<String> = time_labels
+        .iter()
+        .map(|time_label| task.display_label(time_label))
+        .collect();
+    let all_counts: Vec<(String, usize)> = named_counts
+        .iter()
+        .flat_map(|(_, counts)| counts.iter().cloned())
+        .collect();
+    let max_y_axis: usize = calculate_max_y_axis(&all_counts);
+    let minimal = task.minimal.unwrap_or(false);
+
+    root.fill(&task.background_color())?;
+    // Each time bucket is subdivided, so that the bars of all series fit side by side
+    let mut chart = chart_builder(root, task, caption, task.scaled(20))
+        .build_cartesian_2d((0..labels.len() - 1).nested_coord(|_| 0.0..1.0), 0..max_y_axis)?;
+
+    if !minimal {
+        let x_label_formatter = |x: &NestedValue<usize, f64>| match x {
+            NestedValue::Category(index) => labels[*index].clone(),
+            NestedValue::Value(..) => String::from(""),
+        };
+        let mut mesh = chart.configure_mesh();
+        mesh.x_labels(calculate_x_label_count(task, &labels))
+            .x_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .y_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .x_label_formatter(&x_label_formatter);
+        if !task.grid.unwrap_or(true) {
+            mesh.disable_mesh();
+        }
+        mesh.draw()?;
+    }
+
+    let bar_width = 0.8 / named_counts.len() as f64;
+    for (series_index, (name, counts)) in named_counts.iter().enumerate() {
+        let color = task.series_color(series_index, name);
+        let left = 0.1 + series_index as f64 * bar_width;
+        chart
+            .draw_series(counts.iter().enumerate().map(|(index, (_, count))| {
+                Rectangle::new(
+                    [
+                        (NestedValue::Value(index, left), 0),
+                        (NestedValue::Value(index, left + bar_width), *count),
+                    ],
+                    color.filled(),
+                )
+            }))?
+            .label(name.clone())
+            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], color.filled()));
+    }
+    if minimal {
+        return Ok(());
+    }
+    chart
+        .configure_series_labels()
+        .label_font((task.font_family(), task.scaled(25)).into_font())
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    for (index, event_label) in task.event_positions(&time_labels) {
+        let x = NestedValue::Category(index);
+        draw_event(&mut chart, task, event_label, (x.clone(), 0), (x, max_y_axis))?;
+    }
+    Ok(())
+}
+
+/// Plots several named count series, which need to share the same labels, as stacked histogram.
+///
+/// Each series is drawn in the task's custom color of its index, and listed in a legend.
+/// The CSV file contains one row per label and series.
+pub fn stacked_plot(
+    task: &PlotTask,
+    description: &str,
+    named_counts: &[(String, Vec<(String, usize)>)],
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "Plotting {} stacked count series of {} to '{}'.",
+        named_counts.len(),
+        description,
+        task.output_file_name
+    );
+    write_stacked_counts_to_csv(task, named_counts)?;
+    render_to_file(task, |root| {
+        draw_stacked_histogram(root, task, &format!("Slack {} over time", description), named_counts)
+    })
+}
+
+fn draw_stacked_histogram<DB>(
+    root: &DrawingArea<DB, Shift>,
+    task: &PlotTask,
+    caption: &str,
+    named_counts: &[(String, Vec<(String, usize)>)],
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    let time_labels: Vec<String> = named_counts
+        .first()
+        .map(|(_, counts)| counts.iter().map(|(label, _)| label.clone()).collect())
+        .unwrap_or_default();
+    if time_labels.is_empty() {
+        return Err(no_data_error(task));
+    }
+    let labels: Vec<String> = time_labels
+        .iter()
+        .map(|time_label| task.display_label(time_label))
+        .collect();
+    let totals: Vec<(String, usize)> = time_labels
+        .iter()
+        .enumerate()
+        .map(|(index, label)| {
+            let total = named_counts.iter().map(|(_, counts)| counts[index].1).sum();
+            (label.clone(), total)
+        })
+        .collect();
+    let max_y_axis: usize = calculate_max_y_axis(&totals);
+    let minimal = task.minimal.unwrap_or(false);
+
+    root.fill(&task.background_color())?;
+    let mut chart = chart_builder(root, task, caption, task.scaled(20))
+        .build_cartesian_2d((0..labels.len() - 1).nested_coord(|_| 0.0..1.0), 0..max_y_axis)?;
+
+    if !minimal {
+        let x_label_formatter = |x: &NestedValue<usize, f64>| match x {
+            NestedValue::Category(index) => labels[*index].clone(),
+            NestedValue::Value(..) => String::from(""),
+        };
+        let mut mesh = chart.configure_mesh();
+        mesh.x_labels(calculate_x_label_count(task, &labels))
+            .x_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .y_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .x_label_formatter(&x_label_formatter);
+        if !task.grid.unwrap_or(true) {
+            mesh.disable_mesh();
+        }
+        mesh.draw()?;
+    }
+
+    let mut bottoms: Vec<usize> = vec![0; labels.len()];
+    for (series_index, (name, counts)) in named_counts.iter().enumerate() {
+        let color = task.series_color(series_index, name);
+        let segments: Vec<(usize, usize, usize)> = counts
+            .iter()
+            .enumerate()
+            .map(|(index, (_, count))| {
+                let bottom = bottoms[index];
+                bottoms[index] += count;
+                (index, bottom, bottom + count)
+            })
+            .collect();
+        chart
+            .draw_series(
+                segments
+                    .into_iter()
+                    .filter(|(_, bottom, top)| top > bottom)
+                    .map(|(index, bottom, top)| {
+                        Rectangle::new(
+                            [(NestedValue::Value(index, 0.1), bottom), (NestedValue::Value(index, 0.9), top)],
+                            color.filled(),
+                        )
+                    }),
+            )?
+            .label(name.clone())
+            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], color.filled()));
+    }
+    if minimal {
+        return Ok(());
+    }
+    chart
+        .configure_series_labels()
+        .label_font((task.font_family(), task.scaled(25)).into_font())
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    for (index, event_label) in task.event_positions(&time_labels) {
+        let x = NestedValue::Category(index);
+        draw_event(&mut chart, task, event_label, (x.clone(), 0), (x, max_y_axis))?;
+    }
+    Ok(())
+}
+
+/// Plots a matrix of counts (with one row per row label and one column per column label) as heatmap, where the
+/// color of each cell shades from the background to the task's first custom color with increasing count.
+///
+/// The CSV file contains one row per row label, with one column per column label.
+pub fn heatmap_plot(
+    task: &PlotTask,
+    description: &str,
+    row_labels: &[String],
+    column_labels: &[String],
+    matrix: &[Vec<usize>],
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "Plotting {}x{} heatmap of {} to '{}'.",
+        row_labels.len(),
+        column_labels.len(),
+        description,
+        task.output_file_name
+    );
+    let named_series: Vec<(String, Vec<(String, f64)>)> = column_labels
+        .iter()
+        .enumerate()
+        .map(|(column, column_label)| {
+            let values = row_labels
+                .iter()
+                .zip(matrix)
+                .map(|(row_label, counts)| (row_label.clone(), counts[column] as f64))
+                .collect();
+            (column_label.clone(), values)
+        })
+        .collect();
+    write_series_to_csv(task, &named_series)?;
+    // The y axis runs bottom-up, but the first row should be drawn at the top
+    let rows_bottom_up: Vec<String> = row_labels.iter().rev().cloned().collect();
+    let matrix_bottom_up: Vec<Vec<usize>> = matrix.iter().rev().cloned().collect();
+    render_to_file(task, |root| {
+        draw_heatmap(root, task, &format!("Slack {}", description), &rows_bottom_up, column_labels, &matrix_bottom_up)
+    })
+}
+
+fn segment_end(labels: &[String], index: usize) -> SegmentValue<&String> {
+    labels.get(index + 1).map_or(SegmentValue::Last, SegmentValue::Exact)
+}
+
+fn draw_heatmap<DB>(
+    root: &DrawingArea<DB, Shift>,
+    task: &PlotTask,
+    caption: &str,
+    row_labels: &[String],
+    column_labels: &[String],
+    matrix: &[Vec<usize>],
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    if row_labels.is_empty() || column_labels.is_empty() {
+        return Err(no_data_error(task));
+    }
+    let max_count = matrix.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let minimal = task.minimal.unwrap_or(false);
+
+    let background = task.background_color();
+    root.fill(&background)?;
+    let mut builder = chart_builder(root, task, caption, task.scaled(20));
+    if !minimal {
+        // Row labels are words (e.g. weekdays), so they need more space than numbers
+        builder.y_label_area_size(task.scaled(50));
+    }
+    let mut chart = builder.build_cartesian_2d(column_labels.into_segmented(), row_labels.into_segmented())?;
+
+    if !minimal {
+        let label_formatter = |x: &SegmentValue<&String>| match x {
+            SegmentValue::CenterOf(label) => label.to_string(),
+            _ => String::from(""),
+        };
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_labels(column_labels.len())
+            .y_labels(row_labels.len())
+            .x_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .y_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .x_label_formatter(&label_formatter)
+            .y_label_formatter(&label_formatter)
+            .draw()?;
+    }
+
+    let color = task.custom_color(0);
+    let shade = |count: usize| {
+        let share = count as f64 / max_count as f64;
+        let mix = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * share).round() as u8;
+        RGBColor(mix(background.0, color.0), mix(background.1, color.1), mix(background.2, color.2))
+    };
+    let cell_text_style = (task.font_family(), task.scaled(18))
+        .into_text_style(root)
+        .pos(Pos::new(HPos::Center, VPos::Center));
+    for (row_index, (row_label, counts)) in row_labels.iter().zip(matrix).enumerate() {
+        for (column_index, column_label) in column_labels.iter().enumerate() {
+            let count = counts[column_index];
+            chart.draw_series(std::iter::once(Rectangle::new(
+                [
+                    (SegmentValue::Exact(column_label), SegmentValue::Exact(row_label)),
+                    (segment_end(column_labels, column_index), segment_end(row_labels, row_index)),
+                ],
+                shade(count).filled(),
+            )))?;
+            if !minimal && count > 0 {
+                // Counts in dark cells are written in white, so that they remain readable
+                let text_color = if count * 2 > max_count { WHITE } else { BLACK };
+                // The count is drawn in pixel coordinates, centered in its cell
+                let (x, y) =
+                    chart.backend_coord(&(SegmentValue::CenterOf(column_label), SegmentValue::CenterOf(row_label)));
+                let area = chart.plotting_area().strip_coord_spec();
+                let (base_x, base_y) = area.get_base_pixel();
+                area.draw(&Text::new(count.to_string(), (x - base_x, y - base_y), cell_text_style.color(&text_color)))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Plots the ratio between the two count series (for their shared labels) and returns the ratios.
+pub fn ratio_plot(
+    task: &PlotTask,
+    message_pattern1: &str,
+    msg_counts1: &[(String, usize)],
+    message_pattern2: &str,
+    msg_counts2: &[(String, usize)],
+) -> Result<Vec<(String, f64)>, Box<dyn Error>> {
+    ratio_plot_to(&FileSink, task, message_pattern1, msg_counts1, message_pattern2, msg_counts2)
+}
+
+/// Plots the ratios like `ratio_plot`, but writes the image and CSV files to the given sink.
+pub fn ratio_plot_to(
+    sink: &dyn ArtifactSink,
+    task: &PlotTask,
+    message_pattern1: &str,
+    msg_counts1: &[(String, usize)],
+    message_pattern2: &str,
+    msg_counts2: &[(String, usize)],
+) -> Result<Vec<(String, f64)>, Box<dyn Error>> {
+    let (message_counts1, message_counts2, time_series) =
+        consolidated_ratios(task, msg_counts1, msg_counts2);
+    println!(
+        "Plotting ratio between {} (mentioning '{}') and {} messages (mentioning '{}') to '{}'.",
+        message_counts1.len(),
+        message_pattern1,
+        message_counts2.len(),
+        message_pattern2,
+        task.output_file_name,
+    );
+
+    write_message_counts_to_sink(
+        sink,
+        Option::Some("counts-pattern1"),
+        task,
+        &message_counts1,
+    )?;
+    write_message_counts_to_sink(
+        sink,
+        Option::Some("counts-pattern2"),
+        task,
+        &message_counts2,
+    )?;
+
+    #[cfg(debug_assertions)]
+    dbg!(format!(
+        "Time series for file '{}' has {} elements.",
+        &task.output_file_name,
+        time_series.len()
+    ));
+
+    let smoothed_series = task.smoothed(&time_series);
+    if let Some(smoothed_series) = &smoothed_series {
+        write_series_to_sink(
+            sink,
+            task,
+            &[
+                (String::from("ratio"), time_series.clone()),
+                (String::from("smoothed"), smoothed_series.clone()),
+            ],
+        )?;
+    }
+    let html_chart = || {
+        let mut named_series = vec![(String::from("ratio"), time_series.clone())];
+        named_series.extend(smoothed_series.clone().map(|smoothed| (String::from("smoothed"), smoothed)));
+        HtmlChart {
+            caption: ratio_caption(message_pattern1, message_pattern2),
+            mark: "line",
+            named_series,
+        }
+    };
+    render_chart_to_sink(sink, task, html_chart, |root| {
+        draw_ratio_chart(
+            root,
+            task,
+            message_pattern1,
+            message_pattern2,
+            &time_series,
+            smoothed_series.as_deref(),
+        )
+    })?;
+    Ok(time_series)
+}
+
+fn ratio_caption(message_pattern1: &str, message_pattern2: &str) -> String {
+    format!(
+        "Slack ratio between '{}' and '{}' over time",
+        message_pattern1, message_pattern2
+    )
+}
+
+/// Renders the same line chart as `ratio_plot` into PNG bytes, without writing any files.
+pub fn ratio_plot_png(
+    task: &PlotTask,
+    message_pattern1: &str,
+    msg_counts1: &[(String, usize)],
+    message_pattern2: &str,
+    msg_counts2: &[(String, usize)],
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (_, _, time_series) = consolidated_ratios(task, msg_counts1, msg_counts2);
+    let smoothed_series = task.smoothed(&time_series);
+    render_to_png(task, |root| {
+        draw_ratio_chart(
+            root,
+            task,
+            message_pattern1,
+            message_pattern2,
+            &time_series,
+            smoothed_series.as_deref(),
+        )
+    })
+}
+
+/// Consolidates the labels of both count series and calculates the ratios for them.
+#[allow(clippy::type_complexity)]
+fn consolidated_ratios(
+    task: &PlotTask,
+    msg_counts1: &[(String, usize)],
+    msg_counts2: &[(String, usize)],
+) -> (Vec<(String, usize)>, Vec<(String, usize)>, Vec<(String, f64)>) {
+    let (message_counts1, message_counts2) =
+        consolidate_labels(msg_counts1.to_vec(), msg_counts2.to_vec());
+    let shared_labels: Vec<String> = message_counts1
+        .iter()
+        .map(|(label, _)| label.clone())
+        .collect();
+    let time_series: Vec<(String, f64)> =
+        calculate_time_series_ratios(task, &shared_labels, &message_counts1, &message_counts2);
+    (message_counts1, message_counts2, time_series)
+}
+
+/// Draws the ratios as line chart; if smoothed ratios are given, they are drawn on top of the (lighter) ratios.
+fn draw_ratio_chart<DB>(
+    root: &DrawingArea<DB, Shift>,
+    task: &PlotTask,
+    message_pattern1: &str,
+    message_pattern2: &str,
+    time_series: &[(String, f64)],
+    smoothed_series: Option<&[(String, f64)]>,
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    let caption = ratio_caption(message_pattern1, message_pattern2);
+    let Some(smoothed_series) = smoothed_series else {
+        return draw_line_chart(root, task, &caption, time_series);
+    };
+    let window = task.smoothing_window.unwrap_or_default();
+    draw_multi_line_chart(
+        root,
+        task,
+        &caption,
+        &[
+            (String::from("ratio"), time_series.to_vec()),
+            (format!("moving average ({} buckets)", window), smoothed_series.to_vec()),
+        ],
+        &[],
+        1,
+    )
+}
+
+/// Plots a time series of arbitrary (possibly negative) values as a line chart.
+pub fn line_plot(
+    task: &PlotTask,
+    description: &str,
+    time_series: &[(String, f64)],
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "Plotting {} values of {} to '{}'.",
+        time_series.len(),
+        description,
+        task.output_file_name
+    );
+    write_message_counts_to_csv(Option::None, task, time_series)?;
+    let caption = format!("Slack {} over time", description);
+    let html_chart = || HtmlChart {
+        caption: caption.clone(),
+        mark: "line",
+        named_series: vec![(description.to_string(), time_series.to_vec())],
+    };
+    render_chart_to_sink(&FileSink, task, html_chart, |root| {
+        draw_line_chart(root, task, &caption, time_series)
+    })
+}
+
+fn draw_line_chart<DB>(
+    root: &DrawingArea<DB, Shift>,
+    task: &PlotTask,
+    caption: &str,
+    time_series: &[(String, f64)],
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    draw_multi_line_chart(root, task, caption, &[(String::new(), time_series.to_vec())], &[], 0)
+}
+
+/// Plots a time series of averages as a line chart, with error bars of one standard deviation around each average.
+pub fn error_bar_plot(
+    task: &PlotTask,
+    description: &str,
+    time_series: &[(String, f64, f64)],
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "Plotting {} values (with error bars) of {} to '{}'.",
+        time_series.len(),
+        description,
+        task.output_file_name
+    );
+    let averages: Vec<(String, f64)> = time_series
+        .iter()
+        .map(|(label, average, _)| (label.clone(), *average))
+        .collect();
+    let std_devs: Vec<(String, f64)> = time_series
+        .iter()
+        .map(|(label, _, std_dev)| (label.clone(), *std_dev))
+        .collect();
+    write_message_counts_to_csv(Option::None, task, &averages)?;
+    write_message_counts_to_csv(Option::Some("std-dev"), task, &std_devs)?;
+    render_to_file(task, |root| {
+        draw_multi_line_chart(
+            root,
+            task,
+            &format!("Slack {} over time", description),
+            &[(String::new(), averages.clone())],
+            &error_bar_extents(time_series),
+            0,
+        )
+    })
+}
+
+/// Returns the lower and upper end of the error bar (one standard deviation) for each average.
+fn error_bar_extents(time_series: &[(String, f64, f64)]) -> Vec<(f64, f64)> {
+    time_series
+        .iter()
+        .map(|(_, average, std_dev)| (average - std_dev, average + std_dev))
+        .collect()
+}
+
+/// Plots the distribution of the values per time bucket as box plot, with whiskers up to 1.5 times
+/// the interquartile range.
+///
+/// The CSV file contains the lower whisker, the quartiles, and the upper whisker per bucket.
+pub fn box_plot(
+    task: &PlotTask,
+    description: &str,
+    distributions: &[(String, Vec<f64>)],
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "Plotting {} distributions of {} to '{}'.",
+        distributions.len(),
+        description,
+        task.output_file_name
+    );
+    let quartiles: Vec<(String, Quartiles)> = distributions
+        .iter()
+        .filter(|(_, values)| !values.is_empty())
+        .map(|(label, values)| (label.clone(), Quartiles::new(values)))
+        .collect();
+    write_quartiles_to_csv(task, &quartiles)?;
+    render_to_file(task, |root| {
+        draw_box_plot(root, task, &format!("Slack {} over time", description), &quartiles)
+    })
+}
+
+fn write_quartiles_to_csv(task: &PlotTask, quartiles: &[(String, Quartiles)]) -> Result<(), Box<dyn Error>> {
+    if task.skip_output {
+        return Ok(());
+    }
+    let mut wtr = Writer::from_writer(Vec::new());
+    wtr.write_record(["label", "lower_whisker", "q1", "median", "q3", "upper_whisker"])?;
+    for (label, bucket_quartiles) in quartiles {
+        let mut record = vec![label.clone()];
+        record.extend(bucket_quartiles.values().iter().map(|value| value.to_string()));
+        wtr.write_record(&record)?;
+    }
+    FileSink.write(&(task.output_file_name.clone() + ".csv"), &wtr.into_inner()?)
+}
+
+fn draw_box_plot<DB>(
+    root: &DrawingArea<DB, Shift>,
+    task: &PlotTask,
+    caption: &str,
+    quartiles: &[(String, Quartiles)],
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    if quartiles.is_empty() {
+        return Err(no_data_error(task));
+    }
+    let max_value = quartiles
+        .iter()
+        .map(|(_, bucket_quartiles)| bucket_quartiles.values()[4])
+        .fold(0.0, f32::max);
+    // At least 1, so that the y-axis does not collapse if all values are zero
+    let max_y_axis = (max_value * 1.1).max(1.0);
+    let labels: Vec<String> = quartiles.iter().map(|(label, _)| task.display_label(label)).collect();
+    let x_label_count = calculate_x_label_count(task, &labels);
+
+    root.fill(&task.background_color())?;
+    let mut chart = chart_builder(root, task, caption, task.scaled(20))
+        .build_cartesian_2d(labels.into_segmented(), 0f32..max_y_axis)?;
+
+    if !task.minimal.unwrap_or(false) {
+        let mut mesh = chart.configure_mesh();
+        mesh.x_labels(x_label_count)
+            .x_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .y_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .x_label_formatter(&|x: &SegmentValue<&String>| match x {
+                SegmentValue::CenterOf(label) => label.to_string(),
+                _ => String::from(""),
+            })
+            .y_label_formatter(&|y: &f32| format!("{:.0}", y));
+        if !task.grid.unwrap_or(true) {
+            mesh.disable_mesh();
+        }
+        mesh.draw()?;
+    }
+
+    let space_per_label = task.image_dim().0 as f64 * 0.9 / labels.len() as f64;
+    let box_width = ((space_per_label * 0.6) as u32).max(1);
+    let color = task.custom_color(0);
+    chart.draw_series(labels.iter().zip(quartiles).map(|(label, (_, bucket_quartiles))| {
+        Boxplot::new_vertical(SegmentValue::CenterOf(label), bucket_quartiles)
+            .width(box_width)
+            .whisker_width(0.5)
+            .style(color.stroke_width(task.scaled(2)))
+    }))?;
+    Ok(())
+}
+
+/// Plots several named time series, which need to share the same labels, as lines on the same axes.
+///
+/// Each series is drawn in the task's custom color of its index, and listed in a legend.
+pub fn multi_line_plot(
+    task: &PlotTask,
+    description: &str,
+    named_series: &[(String, Vec<(String, f64)>)],
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "Plotting {} series of {} to '{}'.",
+        named_series.len(),
+        description,
+        task.output_file_name
+    );
+    write_series_to_csv(task, named_series)?;
+    render_to_file(task, |root| {
+        draw_multi_line_chart(
+            root,
+            task,
+            &format!("Slack {} over time", description),
+            named_series,
+            &[],
+            0,
+        )
+    })
+}
+
+/// Writes the given times as ISO 8601 timestamps, one per line, into a text file named after the task's
+/// output file and the (sanitized) pattern the times belong to.
+pub(crate) fn write_timestamps(
+    task: &PlotTask,
+    pattern: &str,
+    times: &[DateTime<Utc>],
+) -> Result<(), Box<dyn Error>> {
+    if task.skip_output {
+        return Ok(());
+    }
+    let sanitized_pattern: String = pattern
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let file_name = format!("{}-timestamps-{}.txt", task.output_file_name, sanitized_pattern);
+    let lines: String = times
+        .iter()
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Micros, true) + "\n")
+        .collect();
+    FileSink.write(&file_name, lines.as_bytes())?;
+    println!("Wrote {} timestamps to '{}'.", times.len(), file_name);
+    Ok(())
+}
+
+/// Plots the counts of the given categories as horizontal bar chart, with the first category at the top.
+pub fn bar_plot(
+    task: &PlotTask,
+    description: &str,
+    category_counts: &[(String, usize)],
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "Plotting {} categories of {} to '{}'.",
+        category_counts.len(),
+        description,
+        task.output_file_name
+    );
+    write_message_counts_to_csv(Option::None, task, category_counts)?;
+    render_to_file(task, |root| {
+        draw_bar_chart(root, task, &format!("Slack {}", description), category_counts)
+    })
+}
+
+fn draw_bar_chart<DB>(
+    root: &DrawingArea<DB, Shift>,
+    task: &PlotTask,
+    caption: &str,
+    category_counts: &[(String, usize)],
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    if category_counts.is_empty() {
+        return Err(no_data_error(task));
+    }
+    let max_x_axis = calculate_max_y_axis(category_counts);
+    // The y axis runs bottom-up, but the first category should be drawn at the top
+    let categories: Vec<String> = category_counts.iter().rev().map(|(category, _)| category.clone()).collect();
+    let minimal = task.minimal.unwrap_or(false);
+
+    root.fill(&task.background_color())?;
+    let mut builder = chart_builder(root, task, caption, task.scaled(20));
+    if !minimal {
+        // Categories are names, so their labels need space according to the longest one
+        let max_chars = categories.iter().map(|category| category.chars().count()).max().unwrap_or(0) as u32;
+        builder.y_label_area_size(task.scaled(30).max(task.scaled(14) * max_chars));
+    }
+    let mut chart = builder.build_cartesian_2d(0..max_x_axis, categories.into_segmented())?;
+
+    if !minimal {
+        let mut mesh = chart.configure_mesh();
+        mesh.y_labels(categories.len())
+            .x_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .y_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .y_label_formatter(&|y: &SegmentValue<&String>| match y {
+                SegmentValue::CenterOf(category) => category.to_string(),
+                _ => String::from(""),
+            });
+        if !task.grid.unwrap_or(true) {
+            mesh.disable_mesh();
+        }
+        mesh.draw()?;
+    }
+
+    chart.draw_series(
+        Histogram::horizontal(&chart)
+            .margin(calculate_margin(0.2, categories.len(), task.image_dim().1))
+            .style(task.custom_color(0).filled())
+            .data(categories.iter().zip(category_counts.iter().rev()).map(|(category, (_, count))| (category, *count))),
+    )?;
+    Ok(())
+}
+
+/// Plots the shares of the given categories as a pie chart, with the categories' names and percentages next to the slices.
+pub fn pie_plot(
+    task: &PlotTask,
+    description: &str,
+    category_counts: &[(String, usize)],
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "Plotting {} categories of {} to '{}'.",
+        category_counts.len(),
+        description,
+        task.output_file_name
+    );
+    write_message_counts_to_csv(Option::None, task, category_counts)?;
+    render_to_file(task, |root| {
+        draw_pie_chart(root, task, &format!("Slack {}", description), category_counts)
+    })
+}
+
+fn draw_pie_chart<DB>(
+    root: &DrawingArea<DB, Shift>,
+    task: &PlotTask,
+    caption: &str,
+    category_counts: &[(String, usize)],
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    root.fill(&task.background_color())?;
+    let chart_area = root.titled(caption, (task.font_family(), task.scaled(30)).into_font())?;
+    let (width, height) = chart_area.dim_in_pixel();
+    let center = ((width / 2) as i32, (height / 2) as i32);
+    let radius = width.min(height) as f64 * 0.35;
+    let sizes: Vec<f64> = category_counts.iter().map(|(_, count)| *count as f64).collect();
+    let colors: Vec<RGBColor> = category_counts
+        .iter()
+        .enumerate()
+        .map(|(index, (name, _))| task.series_color(index, name))
+        .collect();
+    let labels: Vec<&str> = category_counts.iter().map(|(name, _)| name.as_str()).collect();
+    let mut pie = Pie::new(&center, &radius, &sizes, &colors, &labels);
+    pie.start_angle(-90.0);
+    pie.label_style((task.font_family(), task.scaled(25)).into_font());
+    pie.percentages((task.font_family(), task.scaled(20)).into_font().color(&WHITE));
+    chart_area.draw(&pie)?;
+    Ok(())
+}
+
+/// Writes a table (e.g. a ranking, which does not fit into a chart) as CSV into the task's output file.
+pub fn table_output(
+    task: &PlotTask,
+    description: &str,
+    header: &[&str],
+    rows: &[Vec<String>],
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "Writing {} rows of {} to '{}'.",
+        rows.len(),
+        description,
+        task.output_file_name
+    );
+    if task.skip_output {
+        return Ok(());
+    }
+    let mut wtr = Writer::from_path(&task.output_file_name)?;
+    wtr.write_record(header)?;
+    for row in rows {
+        wtr.write_record(row)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Writes several named count series with the same labels into a CSV file, with one row per label and series.
+fn write_stacked_counts_to_csv(
+    task: &PlotTask,
+    named_counts: &[(String, Vec<(String, usize)>)],
+) -> Result<(), Box<dyn Error>> {
+    if task.skip_output {
+        return Ok(());
+    }
+    let mut wtr = Writer::from_writer(Vec::new());
+    wtr.write_record(["label", "series", "count"])?;
+    let labels = named_counts.first().map(|(_, counts)| counts.as_slice()).unwrap_or(&[]);
+    for (index, (label, _)) in labels.iter().enumerate() {
+        for (name, counts) in named_counts {
+            wtr.write_record([label, name, &counts[index].1.to_string()])?;
+        }
+    }
+    FileSink.write(&(task.output_file_name.clone() + ".csv"), &wtr.into_inner()?)
+}
+
+/// Writes several named series with the same labels into a CSV file, with one column per series.
+fn write_series_to_csv(
+    task: &PlotTask,
+    named_series: &[(String, Vec<(String, f64)>)],
+) -> Result<(), Box<dyn Error>> {
+    write_series_to_sink(&FileSink, task, named_series)
+}
+
+fn write_series_to_sink(
+    sink: &dyn ArtifactSink,
+    task: &PlotTask,
+    named_series: &[(String, Vec<(String, f64)>)],
+) -> Result<(), Box<dyn Error>> {
+    if task.skip_output {
+        return Ok(());
+    }
+    let mut wtr = Writer::from_writer(Vec::new());
+    let mut header = vec!["label"];
+    header.extend(named_series.iter().map(|(name, _)| name.as_str()));
+    wtr.write_record(&header)?;
+    let labels = named_series.first().map(|(_, series)| series.as_slice()).unwrap_or(&[]);
+    for (index, (label, _)) in labels.iter().enumerate() {
+        let mut record = vec![label.clone()];
+        record.extend(named_series.iter().map(|(_, series)| series[index].1.to_string()));
+        wtr.write_record(&record)?;
+    }
+    sink.write(&(task.output_file_name.clone() + ".csv"), &wtr.into_inner()?)
+}
+
+/// Draws the named series as lines, with optional error bars (lower and upper end) for the values of the first series.
+/// The first `faded_series` series are drawn lighter, e.g. as background for a smoothed version of them.
+fn draw_multi_line_chart<DB>(
+    root: &DrawingArea<DB, Shift>,
+    task: &PlotTask,
+    caption: &str,
+    named_series: &[(String, Vec<(String, f64)>)],
+    error_bars: &[(f64, f64)],
+    faded_series: usize,
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    let labels: Vec<String> = named_series
+        .first()
+        .map(|(_, series)| series.iter().map(|(label, _)| label.clone()).collect())
+        .unwrap_or_default();
+    if labels.is_empty() {
+        return Err(no_data_error(task));
+    }
+    // A single label is padded, as the x-axis would collapse otherwise
+    let padding = task.x_padding.unwrap_or(0).max(usize::from(labels.len() == 1));
+    let values = || {
+        named_series
+            .iter()
+            .flat_map(|(_, series)| series.iter().map(|x| x.1))
+            .chain(error_bars.iter().flat_map(|(low, high)| [*low, *high]))
+    };
+    let max_y_axis: f64 = values().fold(0.0, |acc: f64, x| acc.max(x)) * 1.1;
+    let min_y_axis: f64 = values().fold(0.0, |acc: f64, x| acc.min(x)) * 1.1;
+
+    let minimal = task.minimal.unwrap_or(false);
+
+    root.fill(&task.background_color())?;
+    let margin = calculate_margin(0.1, labels.len(), task.image_dim().0).max(task.scaled(20));
+    let mut chart = chart_builder(root, task, caption, margin)
+        .build_cartesian_2d(padded_x_range(labels.len(), padding), min_y_axis..max_y_axis)?;
+
+    let x_label_formatter = |x: &usize| {
+        // Padding positions have no label
+        match x.checked_sub(padding) {
+            Some(index) if index < labels.len() => task.display_label(&labels[index]),
+            _ => String::from(""),
+        }
+    };
+    if !minimal {
+        let mut mesh = chart.configure_mesh();
+        mesh.x_labels(calculate_x_label_count(task, &labels) + 2 * padding)
+            .x_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .y_label_style((task.font_family(), task.scaled(25)).into_text_style(root))
+            .x_label_formatter(&x_label_formatter);
+        if !task.grid.unwrap_or(true) {
+            mesh.disable_mesh();
+        }
+        mesh.draw()?;
+    }
+    for (series_index, (name, series)) in named_series.iter().enumerate() {
+        let color = task.series_color(series_index, name);
+        let style: ShapeStyle = if series_index < faded_series {
+            color.mix(0.35).into()
+        } else if faded_series > 0 {
+            color.stroke_width(task.scaled(3))
+        } else {
+            color.into()
+        };
+        let line_series_data: Vec<(usize, f64)> = series
+            .iter()
+            .enumerate() // Gives you (index, &(String, f64))
+            .map(|(i, (_, val))| (i + padding, *val)) // Map to (index, f64)
+            .collect();
+        // Lines need two points, so single values are marked instead
+        if let [(x, y)] = line_series_data[..] {
+            chart.draw_series(std::iter::once(Circle::new((x, y), task.scaled(5), style.filled())))?;
+        }
+        chart
+            .draw_series(LineSeries::new(line_series_data, style))?
+            .label(name.clone())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], style));
+    }
+    chart.draw_series(error_bars.iter().enumerate().map(|(index, (low, high))| {
+        ErrorBar::new_vertical(
+            index + padding,
+            *low,
+            (low + high) / 2.0,
+            *high,
+            task.custom_color(0).filled(),
+            task.scaled(10),
         )
-        .unwrap();
-    root.present()?;
+    }))?;
+    if minimal {
+        return Ok(());
+    }
+    if named_series.len() > 1 {
+        chart
+            .configure_series_labels()
+            .label_font((task.font_family(), task.scaled(25)).into_font())
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+    }
+    for (index, event_label) in task.event_positions(&labels) {
+        let x = index + padding;
+        draw_event(&mut chart, task, event_label, (x, min_y_axis), (x, max_y_axis))?;
+    }
     Ok(())
 }
 
-pub fn ratio_plot(
-    task: &PlotTask,
-    message_pattern1: &str,
-    msg_counts1: &Vec<(String, usize)>,
-    message_pattern2: &str,
-    msg_counts2: &Vec<(String, usize)>,
-) -> Result<(), Box<dyn Error>> {
-    let (message_counts1, message_counts2) =
-        consolidate_labels(msg_counts1.clone(), msg_counts2.clone());
-    let shared_labels: Vec<String> = message_counts1
-        .iter()
-        .map(|(label, _)| label.clone())
-        .collect();
-    println!(
-        "Plotting ratio between {} (mentioning '{}') and {} messages (mentioning '{}') to '{}'.",
-        message_counts1.len(),
-        message_pattern1,
-        message_counts2.len(),
-        message_pattern2,
-        task.output_file_name,
-    );
+/// The error for charts without any data, e.g. as the task's patterns match no messages.
+fn no_data_error(task: &PlotTask) -> Box<dyn Error> {
+    format!(
+        "No data to plot to '{}', check whether the task's patterns match any messages.",
+        task.output_file_name
+    )
+    .into()
+}
 
-    write_message_counts_to_csv(
-        Option::Some("counts-pattern1"),
-        &task.output_file_name,
-        &message_counts1,
-    )?;
-    write_message_counts_to_csv(
-        Option::Some("counts-pattern2"),
-        &task.output_file_name,
-        &message_counts2,
-    )?;
+/// Creates the builder for a chart with the given caption and margin, and room for the axis labels.
+/// Minimal charts have neither, so that the data is drawn edge-to-edge.
+fn chart_builder<'a, 'b, DB: DrawingBackend>(
+    root: &'a DrawingArea<DB, Shift>,
+    task: &'b PlotTask,
+    caption: &str,
+    margin: u32,
+) -> ChartBuilder<'a, 'b, DB> {
+    let mut builder = ChartBuilder::on(root);
+    if !task.minimal.unwrap_or(false) {
+        builder
+            .margin(margin)
+            .caption(caption, (task.font_family(), task.scaled(30)).into_font())
+            .x_label_area_size(task.scaled(30))
+            .y_label_area_size(task.scaled(30));
+    }
+    builder
+}
 
-    let time_series: Vec<(String, f64)> =
-        calculate_time_series_ratios(&shared_labels, &message_counts1, &message_counts2);
+/// The range of x positions for the given number of labels, with `padding` empty positions on either side.
+/// Both ends of the range are positions on the axis.
+fn padded_x_range(num_labels: usize, padding: usize) -> std::ops::Range<usize> {
+    0..(num_labels + 2 * padding).saturating_sub(1)
+}
 
-    #[cfg(debug_assertions)]
-    dbg!(format!(
-        "Time series for file '{}' has {} elements.",
-        &task.output_file_name,
-        time_series.len()
-    ));
+/// The color of a series if the task defines none: blue for the first, palette colors for the others.
+fn default_series_color(index: usize) -> RGBColor {
+    if index == 0 {
+        BLUE
+    } else {
+        let (r, g, b) = Palette99::COLORS[(index - 1) % Palette99::COLORS.len()];
+        RGBColor(r, g, b)
+    }
+}
+
+/// Renders a chart into the task's output file, in the task's output format.
+fn render_to_file<F>(task: &PlotTask, draw: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnOnce(&DrawingArea<FileBackend, Shift>) -> Result<(), Box<dyn Error>>,
+{
+    render_to_sink(&FileSink, task, draw)
+}
+
+/// Renders a chart in the task's output format, and writes it to the sink (named after the task's output file).
+fn render_to_sink<F>(sink: &dyn ArtifactSink, task: &PlotTask, draw: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnOnce(&DrawingArea<FileBackend, Shift>) -> Result<(), Box<dyn Error>>,
+{
+    if task.skip_output || task.skip_image {
+        return Ok(());
+    }
+    match task.output_format() {
+        OutputFormat::Html => Err(format!(
+            "Task '{}' cannot be rendered as HTML, as this is only supported for single series and ratios.",
+            task.output_file_name
+        )
+        .into()),
+        OutputFormat::Png => sink.write(&task.output_file_name, &render_to_png(task, draw)?),
+        OutputFormat::Svg => {
+            let mut svg = String::new();
+            {
+                let root = FileBackend::Svg(SVGBackend::with_string(&mut svg, task.image_dim())).into_drawing_area();
+                draw(&root)?;
+                root.present()?;
+            }
+            sink.write(&task.output_file_name, svg.as_bytes())
+        }
+    }
+}
+
+/// The data of a chart, for rendering it as interactive HTML.
+struct HtmlChart {
+    caption: String,
+    /// The Vega-Lite mark to draw the series with, e.g. `bar` or `line`.
+    mark: &'static str,
+    named_series: Vec<(String, Vec<(String, f64)>)>,
+}
 
-    let line_series_data: Vec<(usize, f64)> = time_series
+/// Renders a chart like `render_to_sink`, or as interactive HTML page if that is the task's output format.
+fn render_chart_to_sink<H, F>(sink: &dyn ArtifactSink, task: &PlotTask, html_chart: H, draw: F) -> Result<(), Box<dyn Error>>
+where
+    H: FnOnce() -> HtmlChart,
+    F: FnOnce(&DrawingArea<FileBackend, Shift>) -> Result<(), Box<dyn Error>>,
+{
+    if task.output_format() != OutputFormat::Html || task.skip_output || task.skip_image {
+        return render_to_sink(sink, task, draw);
+    }
+    sink.write(&task.output_file_name, render_html(task, &html_chart())?.as_bytes())
+}
+
+/// Renders the chart as HTML page with a Vega-Lite specification, which shows the exact values as tooltips.
+fn render_html(task: &PlotTask, chart: &HtmlChart) -> Result<String, Box<dyn Error>> {
+    let values: Vec<serde_json::Value> = chart
+        .named_series
         .iter()
-        .enumerate() // Gives you (index, &(String, f64))
-        .map(|(i, (_, val))| (i, *val)) // Map to (index, f64)
+        .flat_map(|(name, series)| {
+            series.iter().map(move |(label, value)| {
+                serde_json::json!({"label": task.display_label(label), "series": name, "value": value})
+            })
+        })
         .collect();
-    let max_y_axis: f64 = time_series
+    let colors: Vec<String> = chart
+        .named_series
         .iter()
-        .map(|x| x.1)
-        .fold(0.0, |acc: f64, x| acc.max(x))
-        * 1.1;
-
-    let root = BitMapBackend::new(&task.output_file_name, DEFAULT_IMAGE_DIM).into_drawing_area();
-    root.fill(&WHITE)?;
-    let mut chart = ChartBuilder::on(&root)
-        .margin(calculate_margin(0.1, message_counts1.len()))
-        .caption(
-            format!(
-                "Slack ratio between '{}' and '{}' over time",
-                message_pattern1, message_pattern2
-            ),
-            ("sans-serif", 30).into_font(),
-        )
-        .x_label_area_size(30)
-        .y_label_area_size(30)
-        .build_cartesian_2d(0..(shared_labels.len() - 1), 0.0..max_y_axis)?;
-
-    chart
-        .configure_mesh()
-        .x_label_style(("sans-serif", 25).into_text_style(&root))
-        .y_label_style(("sans-serif", 25).into_text_style(&root))
-        .x_label_formatter(&|x| {
-            let index: usize = *x;
-            if index < shared_labels.len() {
-                shared_labels[index].clone()
-            } else {
-                String::from("")
-            }
+        .enumerate()
+        .map(|(index, (name, _))| {
+            let color = task.series_color(index, name);
+            format!("#{:02x}{:02x}{:02x}", color.0, color.1, color.2)
         })
-        .draw()?;
-    chart.draw_series(LineSeries::new(line_series_data, task.custom_color(0)))?;
-    root.present()?;
+        .collect();
+    let (_, height) = task.image_dim();
+    let mut spec = serde_json::json!({
+        "$schema": "https://vega.github.io/schema/vega-lite/v5.json",
+        "title": chart.caption,
+        "width": "container",
+        "height": height / 2,
+        "data": {"values": values},
+        "mark": {"type": chart.mark, "tooltip": true, "point": chart.mark == "line"},
+        "encoding": {
+            // Labels are sortable, so they keep the order of the buckets
+            "x": {"field": "label", "type": "ordinal", "title": null},
+            "y": {"field": "value", "type": "quantitative", "title": null},
+            "color": {
+                "field": "series",
+                "type": "nominal",
+                "title": null,
+                "scale": {"domain": chart.named_series.iter().map(|(name, _)| name).collect::<Vec<_>>(), "range": colors},
+                "legend": if chart.named_series.len() > 1 { serde_json::json!({}) } else { serde_json::Value::Null },
+            },
+            "tooltip": [
+                {"field": "label", "title": "bucket"},
+                {"field": "series"},
+                {"field": "value", "type": "quantitative"},
+            ],
+        },
+    });
+    if let Some(background) = &task.background {
+        spec["background"] = serde_json::json!(format!("#{}", background.trim_start_matches('#')));
+    }
+    // Embedded into a script element, which must not be closed by any string in the specification
+    let spec = serde_json::to_string(&spec)?.replace("</", "<\\/");
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <script src=\"https://cdn.jsdelivr.net/npm/vega@5\"></script>\n\
+         <script src=\"https://cdn.jsdelivr.net/npm/vega-lite@5\"></script>\n\
+         <script src=\"https://cdn.jsdelivr.net/npm/vega-embed@6\"></script>\n</head>\n\
+         <body>\n<div id=\"chart\" style=\"width: 100%\"></div>\n\
+         <script>vegaEmbed('#chart', {});</script>\n</body>\n</html>\n",
+        crate::output::escape_html(&chart.caption),
+        spec
+    ))
+}
+
+/// Renders a chart into an in-memory RGB buffer and encodes it as PNG.
+fn render_to_png<F>(task: &PlotTask, draw: F) -> Result<Vec<u8>, Box<dyn Error>>
+where
+    F: FnOnce(&DrawingArea<FileBackend, Shift>) -> Result<(), Box<dyn Error>>,
+{
+    let (width, height) = task.image_dim();
+    let mut buffer: Vec<u8> = vec![0; (width * height * 3) as usize];
+    {
+        let root = FileBackend::Bitmap(BitMapBackend::with_buffer(&mut buffer, (width, height))).into_drawing_area();
+        draw(&root)?;
+        root.present()?;
+    }
+    let mut png: Vec<u8> = Vec::new();
+    PngEncoder::new(&mut png).write_image(&buffer, width, height, ColorType::Rgb8)?;
+    Ok(png)
+}
+
+/// Draws an event as a dashed vertical line from `bottom` to `top`, labeled at the top.
+fn draw_event<DB, X, Y>(
+    chart: &mut ChartContext<'_, DB, Cartesian2d<X, Y>>,
+    task: &PlotTask,
+    event_label: String,
+    bottom: (X::ValueType, Y::ValueType),
+    top: (X::ValueType, Y::ValueType),
+) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+    X: Ranged,
+    Y: Ranged,
+    X::ValueType: Clone,
+    Y::ValueType: Clone,
+{
+    let top_coord = chart.backend_coord(&top);
+    chart.draw_series(DashedLineSeries::new(
+        vec![bottom, top],
+        task.scaled(10),
+        task.scaled(5),
+        BLACK.stroke_width(task.scaled(2)),
+    ))?;
+    // The label is drawn in pixel coordinates, right next to the top of the line
+    let (x, y) = top_coord;
+    let area = chart.plotting_area().strip_coord_spec();
+    let (base_x, base_y) = area.get_base_pixel();
+    area.draw(&Text::new(
+        event_label,
+        (x - base_x + task.scaled(5) as i32, y - base_y),
+        (task.font_family(), task.scaled(20)).into_font(),
+    ))?;
     Ok(())
 }
 
-fn calculate_margin(ratio: f64, num_labels: usize) -> u32 {
-    (ratio * ((DEFAULT_IMAGE_DIM.0 as f64 * 0.9) / (num_labels as f64))) as u32
+/// Calculates a margin as a fraction of the space per label, keeping at least one pixel
+/// as long as there are enough pixels per label to still show something in between (e.g. for hourly histograms).
+fn calculate_margin(ratio: f64, num_labels: usize, image_width: u32) -> u32 {
+    let space_per_label = (image_width as f64 * 0.9) / (num_labels as f64);
+    let margin = (ratio * space_per_label) as u32;
+    if margin == 0 && space_per_label >= 3.0 { 1 } else { margin }
+}
+
+/// Calculates how many x-axis labels fit next to each other without overlapping, at most one per time bucket.
+fn calculate_x_label_count(task: &PlotTask, labels: &[String]) -> usize {
+    let max_chars = labels.iter().map(|label| label.chars().count()).max().unwrap_or(1);
+    // Characters are roughly 0.6 times as wide as the font size, plus some space between the labels
+    let label_width = (max_chars + 4) as f64 * task.scaled(25) as f64 * 0.6;
+    let fitting_labels = (task.image_dim().0 as f64 * 0.9 / label_width) as usize;
+    fitting_labels.clamp(1, labels.len().max(1))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
 
     #[test]
     fn test_read_analysis_tasks_from_file() {
@@ -308,8 +2634,8 @@ mod tests {
         }
         assert_eq!(tasks[0].resolution, TimeResolution::Daily);
         assert_eq!(tasks[0].output_file_name, "./tests/output/group-mentions.png");
-        assert_eq!(tasks[0].colors, 
-            Some(vec!["#e27505".to_string(), "#55332c".to_string(), "#505050".to_string()]));
+        assert_eq!(tasks[0].colors, Some(Colors::List(
+            vec!["#e27505".to_string(), "#55332c".to_string(), "#505050".to_string()])));
 
         match &tasks[1].metric {
             Metric::StringMessageCountRatio {
@@ -361,6 +2687,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_consolidated_ratios_with_empty_buckets() {
+        let task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Monthly,
+            "ratios.png",
+        );
+        let (counts1, counts2, ratios) = consolidated_ratios(
+            &task,
+            &[("2024-01".to_string(), 1), ("2024-02".to_string(), 0), ("2024-03".to_string(), 3)],
+            &[("2024-02".to_string(), 0), ("2024-03".to_string(), 1), ("2024-04".to_string(), 2)],
+        );
+        assert_eq!(counts1, vec![("2024-02".to_string(), 0), ("2024-03".to_string(), 3)]);
+        assert_eq!(counts2, vec![("2024-02".to_string(), 0), ("2024-03".to_string(), 1)]);
+        assert_eq!(ratios, vec![("2024-02".to_string(), 0.0), ("2024-03".to_string(), 0.75)]);
+    }
+
     #[test]
     fn test_rgb_from_hex() {
         let result = PlotTask::rgb_from_hex("#007f94");
@@ -371,4 +2717,599 @@ mod tests {
     fn test_rgb_from_hex_invalid() {
         assert!(PlotTask::rgb_from_hex("#007f9").is_err()); // Invalid length
     }
+
+    #[test]
+    fn test_counter_plot_scale() {
+        let output_dir = std::env::temp_dir().join("slackrs-test-counter-plot-scale");
+        fs::create_dir_all(&output_dir).unwrap();
+        let mut task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Daily,
+            output_dir.join("scaled.png").to_str().unwrap(),
+        );
+        task.scale = Some(2.0);
+        let message_counts = vec![("2025-01-01".to_string(), 1), ("2025-01-02".to_string(), 3)];
+        counter_plot(&task, "test messages", &message_counts).expect("Plotting failed");
+
+        // Width and height are stored as big-endian integers in the PNG header (IHDR chunk)
+        let png = fs::read(&task.output_file_name).unwrap();
+        let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+        let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+        assert_eq!((width, height), (2 * DEFAULT_IMAGE_DIM.0, 2 * DEFAULT_IMAGE_DIM.1));
+    }
+
+    #[test]
+    fn test_weekend_positions() {
+        let mut task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Daily,
+            "test.png",
+        );
+        // 2025-01-03 is a Friday
+        let labels: Vec<String> = ["2025-01-03", "2025-01-04", "2025-01-05", "2025-01-06", "2025-01-11"]
+            .iter()
+            .map(|label| label.to_string())
+            .collect();
+        assert!(task.weekend_positions(&labels).is_empty());
+
+        task.weekend_shading = Some(true);
+        assert_eq!(task.weekend_positions(&labels), vec![1, 2, 4]);
+
+        task.resolution = TimeResolution::Weekly;
+        assert!(task.weekend_positions(&["2025-W01".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn test_counter_plot_weekend_shading() {
+        let output_dir = std::env::temp_dir().join("slackrs-test-weekend-shading");
+        fs::create_dir_all(&output_dir).unwrap();
+        let mut task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Daily,
+            output_dir.join("weekends.png").to_str().unwrap(),
+        );
+        task.weekend_shading = Some(true);
+        let message_counts: Vec<(String, usize)> = (1..=14)
+            .map(|day| (format!("2025-01-{:02}", day), day % 5))
+            .collect();
+        counter_plot(&task, "test messages", &message_counts).expect("Plotting failed");
+        assert!(Path::new(&task.output_file_name).is_file());
+    }
+
+    #[test]
+    fn test_plots_of_empty_and_single_buckets() {
+        let output_dir = std::env::temp_dir().join("slackrs-test-empty-plots");
+        fs::create_dir_all(&output_dir).unwrap();
+        let task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Daily,
+            output_dir.join("plot.png").to_str().unwrap(),
+        );
+
+        let error = counter_plot(&task, "test messages", &[]).unwrap_err();
+        assert!(error.to_string().starts_with("No data to plot"));
+        // The (empty) CSV file is still written
+        assert!(output_dir.join("plot.png.csv").is_file());
+        assert!(ratio_plot(&task, "a", &[], "b", &[]).is_err());
+        assert!(ratio_plot(&task, "a", &[("2025-01-01".to_string(), 1)], "b", &[]).is_err());
+
+        for count in [0, 3] {
+            let single_bucket = vec![("2025-01-01".to_string(), count)];
+            counter_plot(&task, "test messages", &single_bucket).expect("Plotting failed");
+            assert!(
+                ratio_plot(&task, "a", &single_bucket, "b", &single_bucket).is_ok_and(|ratios| ratios.len() == 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_minimal() {
+        let mut task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Daily,
+            "minimal.png",
+        );
+        let message_counts = vec![("2025-01-01".to_string(), 1), ("2025-01-02".to_string(), 3)];
+        // Axes, labels, and caption are black, unlike the (blue) data and the (white) background
+        let has_black_pixels = |png: Vec<u8>| {
+            let image = image::load_from_memory(&png).unwrap().to_rgb8();
+            assert_eq!(image.dimensions(), DEFAULT_IMAGE_DIM);
+            image.pixels().any(|pixel| pixel.0.iter().all(|channel| *channel < 100))
+        };
+        let plots = |task: &PlotTask| {
+            [
+                counter_plot_png(task, "test messages", &message_counts).expect("Plotting failed"),
+                ratio_plot_png(task, "a", &message_counts, "b", &message_counts).expect("Plotting failed"),
+            ]
+        };
+        assert!(plots(&task).into_iter().all(has_black_pixels));
+
+        task.minimal = Some(true);
+        assert!(!plots(&task).into_iter().any(has_black_pixels));
+    }
+
+    #[test]
+    fn test_output_format() {
+        let output_dir = std::env::temp_dir().join("slackrs-test-output-format");
+        fs::create_dir_all(&output_dir).unwrap();
+        let message_counts = vec![("2025-01-01".to_string(), 1), ("2025-01-02".to_string(), 3)];
+        let task_for = |file_name: &str, output_format: Option<OutputFormat>| {
+            let mut task = PlotTask::new(
+                Metric::MentionCount {
+                    channel_pattern: "".to_string(),
+                    message_pattern: "".to_string(),
+                },
+                TimeResolution::Daily,
+                output_dir.join(file_name).to_str().unwrap(),
+            );
+            task.output_format = output_format;
+            task
+        };
+
+        let png_task = task_for("counts.png", None);
+        let svg_task = task_for("counts.SVG", None);
+        let overridden_task = task_for("counts.svg.out", Some(OutputFormat::Svg));
+        let png_without_extension_task = task_for("counts", None);
+        assert_eq!(png_task.output_format(), OutputFormat::Png);
+        assert_eq!(svg_task.output_format(), OutputFormat::Svg);
+        assert_eq!(overridden_task.output_format(), OutputFormat::Svg);
+        assert_eq!(png_without_extension_task.output_format(), OutputFormat::Png);
+
+        for task in [&png_task, &png_without_extension_task] {
+            counter_plot(task, "test messages", &message_counts).expect("Plotting failed");
+            let png = fs::read(&task.output_file_name).unwrap();
+            assert!(png.starts_with(b"\x89PNG"));
+        }
+        for task in [&svg_task, &overridden_task] {
+            counter_plot(task, "test messages", &message_counts).expect("Plotting failed");
+            let svg = fs::read_to_string(&task.output_file_name).unwrap();
+            assert!(svg.starts_with("<svg"));
+            assert!(svg.contains("Slack test messages over time"));
+        }
+    }
+
+    #[test]
+    fn test_event_positions() {
+        let mut task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Monthly,
+            "events.png",
+        );
+        let labels = vec!["2025-01".to_string(), "2025-02".to_string(), "2025-03".to_string()];
+        assert_eq!(task.event_positions(&labels), vec![]);
+
+        task = task.with_events(&[
+            Event { date: "2025-02-14".to_string(), label: "Release 1.0".to_string() },
+            Event { date: "2025-04-01".to_string(), label: "Out of range".to_string() },
+            Event { date: "invalid".to_string(), label: "Invalid".to_string() },
+        ]);
+        assert_eq!(task.event_positions(&labels), vec![(1, "Release 1.0".to_string())]);
+    }
+
+    #[test]
+    fn test_plots_to_png_bytes() {
+        let task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Daily,
+            "not-written.png",
+        );
+        let counts1 = vec![("2025-01-01".to_string(), 1), ("2025-01-02".to_string(), 3)];
+        let counts2 = vec![("2025-01-01".to_string(), 2), ("2025-01-02".to_string(), 1)];
+        let png_signature: &[u8] = &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+        let counter_png = counter_plot_png(&task, "test messages", &counts1).expect("Plotting failed");
+        assert!(counter_png.starts_with(png_signature));
+        let ratio_png =
+            ratio_plot_png(&task, "a", &counts1, "b", &counts2).expect("Plotting failed");
+        assert!(ratio_png.starts_with(png_signature));
+        assert!(!Path::new("not-written.png").exists());
+    }
+
+    #[test]
+    fn test_ratio_min_denominator() {
+        let mut task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Daily,
+            "ratios.png",
+        );
+        let labels = vec!["2025-01-01".to_string(), "2025-01-02".to_string()];
+        let counts1 = vec![(labels[0].clone(), 1), (labels[1].clone(), 1)];
+        let counts2 = vec![(labels[0].clone(), 0), (labels[1].clone(), 3)];
+        assert_eq!(
+            calculate_time_series_ratios(&task, &labels, &counts1, &counts2),
+            vec![(labels[0].clone(), 1.0), (labels[1].clone(), 0.25)]
+        );
+
+        task.min_denominator = Some(2);
+        assert_eq!(
+            calculate_time_series_ratios(&task, &labels, &counts1, &counts2),
+            vec![(labels[1].clone(), 0.25)]
+        );
+
+        task.min_denominator_mode = Some(SuppressionMode::Zero);
+        assert_eq!(
+            calculate_time_series_ratios(&task, &labels, &counts1, &counts2),
+            vec![(labels[0].clone(), 0.0), (labels[1].clone(), 0.25)]
+        );
+    }
+
+    #[test]
+    fn test_threshold_color() {
+        let mut task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Daily,
+            "thresholds.png",
+        );
+        assert_eq!(task.threshold_color(5.0), None);
+
+        task.color_thresholds = Some(vec![(10.0, "#ff0000".to_string()), (0.0, "#00ff00".to_string())]);
+        assert_eq!(task.threshold_color(0.0), Some(RGBColor(0, 255, 0)));
+        assert_eq!(task.threshold_color(9.0), Some(RGBColor(0, 255, 0)));
+        assert_eq!(task.threshold_color(10.0), Some(RGBColor(255, 0, 0)));
+        assert_eq!(task.threshold_color(42.0), Some(RGBColor(255, 0, 0)));
+        assert_eq!(task.threshold_color(-1.0), None);
+    }
+
+    #[test]
+    fn test_series_color() {
+        let mut task = PlotTask::new(
+            Metric::TermAdoption {
+                channel_pattern: "".to_string(),
+                terms: vec!["general".to_string(), "random".to_string()],
+            },
+            TimeResolution::Daily,
+            "series-colors.png",
+        );
+        task.colors = serde_json::from_str(r##"{"general": "#ff0000"}"##).unwrap();
+        assert_eq!(task.series_color(0, "general"), RGBColor(255, 0, 0));
+        assert_eq!(task.series_color(1, "random"), default_series_color(1));
+
+        task.colors = serde_json::from_str(r##"["#00ff00"]"##).unwrap();
+        assert_eq!(task.series_color(0, "general"), RGBColor(0, 255, 0));
+        assert_eq!(task.series_color(1, "random"), default_series_color(1));
+    }
+
+    #[test]
+    fn test_with_theme() {
+        let theme_file = std::env::temp_dir().join("slackrs-test-theme.json");
+        fs::write(
+            &theme_file,
+            r##"{
+                "named_colors": {"brand": "#e27505", "ink": "#202020"},
+                "colors": ["brand", "#505050"],
+                "background": "#fafafa",
+                "font": "serif",
+                "grid": false
+            }"##,
+        )
+        .unwrap();
+        let theme = read_theme_from_file(&theme_file).expect("Failed to read theme");
+        let mut task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Daily,
+            "themed.png",
+        );
+        let themed_task = task.with_theme(&theme);
+        assert_eq!(themed_task.background_color(), RGBColor(250, 250, 250));
+        assert_eq!(themed_task.font_family(), "serif");
+        assert_eq!(themed_task.grid, Some(false));
+        assert_eq!(
+            themed_task.colors,
+            Some(Colors::List(vec!["#e27505".to_string(), "#505050".to_string()]))
+        );
+
+        task.background = Some("ink".to_string());
+        task.grid = Some(true);
+        let themed_task = task.with_theme(&theme);
+        assert_eq!(themed_task.background_color(), RGBColor(32, 32, 32));
+        assert_eq!(themed_task.grid, Some(true));
+        fs::remove_file(&theme_file).unwrap();
+    }
+
+    #[test]
+    fn test_next_label() {
+        assert_eq!(
+            TimeResolution::Hourly.next_label("2024-12-31 23:00"),
+            Some("2025-01-01 00:00".to_string())
+        );
+        assert_eq!(TimeResolution::Daily.next_label("2024-02-28"), Some("2024-02-29".to_string()));
+        assert_eq!(TimeResolution::Weekly.next_label("2024-W52"), Some("2025-W01".to_string()));
+        assert_eq!(TimeResolution::Monthly.next_label("2024-12"), Some("2025-01".to_string()));
+        assert_eq!(TimeResolution::Yearly.next_label("2024"), Some("2025".to_string()));
+        assert_eq!(TimeResolution::Monthly.next_label("2024-12-01"), None);
+    }
+
+    #[test]
+    fn test_hourly_and_weekly_labels() {
+        let time = Utc.with_ymd_and_hms(2025, 1, 1, 13, 45, 0).unwrap();
+        assert_eq!(TimeResolution::Hourly.label(&time), "2025-01-01 13:00");
+        assert_eq!(TimeResolution::Weekly.label(&time), "2025-W01");
+        // ISO weeks belong to the year of their Thursday, so they can start or end in the neighboring year
+        let last_sunday_of_2024 = Utc.with_ymd_and_hms(2024, 12, 29, 12, 0, 0).unwrap();
+        assert_eq!(TimeResolution::Weekly.label(&last_sunday_of_2024), "2024-W52");
+        let last_monday_of_2024 = Utc.with_ymd_and_hms(2024, 12, 30, 12, 0, 0).unwrap();
+        assert_eq!(TimeResolution::Weekly.label(&last_monday_of_2024), "2025-W01");
+        let new_year_2021 = Utc.with_ymd_and_hms(2021, 1, 1, 12, 0, 0).unwrap();
+        assert_eq!(TimeResolution::Weekly.label(&new_year_2021), "2020-W53");
+        assert_eq!(TimeResolution::Weekly.next_label("2020-W53"), Some("2021-W01".to_string()));
+        assert_eq!(
+            TimeResolution::Hourly.start_time_of("2025-01-01 13:00"),
+            NaiveDate::from_ymd_opt(2025, 1, 1).and_then(|date| date.and_hms_opt(13, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_margin_and_x_labels_for_many_hourly_labels() {
+        let mut hourly_task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Hourly,
+            "hourly.png",
+        );
+        let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let labels: Vec<String> = (0..500)
+            .map(|hours| TimeResolution::Hourly.label(&(start + TimeDelta::hours(hours))))
+            .collect();
+        let image_width = hourly_task.image_dim().0;
+
+        // A few pixels per label still leave a margin between the bars
+        assert_eq!(calculate_margin(0.2, labels.len(), image_width), 1);
+        assert_eq!(calculate_margin(0.2, 10, image_width), 36);
+        // Without enough pixels per label, bars are drawn without a margin
+        assert_eq!(calculate_margin(0.2, 1000, image_width), 0);
+
+        let label_count = calculate_x_label_count(&hourly_task, &labels);
+        assert!((1..=20).contains(&label_count), "{} labels would overlap", label_count);
+        hourly_task.scale = Some(2.0);
+        assert_eq!(calculate_x_label_count(&hourly_task, &labels), label_count);
+        assert_eq!(calculate_x_label_count(&hourly_task, &labels[..2]), 2);
+    }
+
+    #[test]
+    fn test_display_label() {
+        let mut task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Monthly,
+            "labels.png",
+        );
+        assert_eq!(task.display_label("2024-01"), "2024-01");
+
+        task.label_format = Some("%b %Y".to_string());
+        let mut labels = ["2024-02", "2023-12", "2024-01"];
+        labels.sort();
+        let display_labels: Vec<String> = labels.iter().map(|label| task.display_label(label)).collect();
+        assert_eq!(display_labels, vec!["Dec 2023", "Jan 2024", "Feb 2024"]);
+        assert_eq!(task.display_label("13"), "13");
+
+        task.resolution = TimeResolution::Weekly;
+        task.label_format = Some("%d.%m.%Y".to_string());
+        assert_eq!(task.display_label("2025-W02"), "06.01.2025");
+    }
+
+    #[test]
+    fn test_error_bar_extents() {
+        let time_series = vec![
+            ("2025-01".to_string(), 2.0, 1.0),
+            ("2025-02".to_string(), 3.0, 0.0),
+        ];
+        assert_eq!(error_bar_extents(&time_series), vec![(1.0, 3.0), (3.0, 3.0)]);
+    }
+
+    #[test]
+    fn test_padded_x_range() {
+        let range = padded_x_range(5, 2);
+        assert_eq!(range.end - range.start + 1, 5 + 2 * 2);
+        let unpadded = padded_x_range(5, 0);
+        assert_eq!(unpadded.end - unpadded.start + 1, 5);
+    }
+
+    #[test]
+    fn test_validate_percentile() {
+        let metric = |percentile| Metric::MessageLengthPercentile {
+            channel_pattern: "".to_string(),
+            message_pattern: "".to_string(),
+            percentile,
+        };
+        assert!(metric(90.0).validate().is_ok());
+        assert!(metric(0.0).validate().is_ok());
+        assert!(metric(100.5).validate().is_err());
+        assert!(metric(-1.0).validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_patterns() {
+        let mut task = PlotTask::new(
+            Metric::StringMessageCountRatio {
+                channel_pattern: "dev".to_string(),
+                message_pattern1: "bug|issue".to_string(),
+                message_pattern2: "fix(ed".to_string(),
+            },
+            TimeResolution::Daily,
+            "patterns.png",
+        );
+        assert!(task.validate().is_ok());
+        task.match_mode = Some(MatchMode::Regex);
+        let error = task.validate().expect_err("Invalid regular expression must be rejected");
+        assert!(error.to_string().contains("'fix(ed'"));
+    }
+
+    #[test]
+    fn test_parse_time_zone() {
+        assert_eq!(parse_time_zone("UTC").unwrap(), Tz::UTC);
+        assert_eq!(parse_time_zone("Europe/Berlin").unwrap(), Tz::Europe__Berlin);
+        assert!(parse_time_zone("+01:00").is_err());
+    }
+
+    #[test]
+    fn test_parse_pattern_groups() {
+        let metric: Metric = serde_json::from_str(
+            r#"{"PatternGroupCount": {"channel_pattern": "", "pattern_groups": {"infra": ["kubernetes", "aws"], "data": ["dbt"]}}}"#,
+        )
+        .expect("Failed to parse metric");
+        assert_eq!(
+            metric,
+            Metric::PatternGroupCount {
+                channel_pattern: "".to_string(),
+                pattern_groups: BTreeMap::from([
+                    ("data".to_string(), vec!["dbt".to_string()]),
+                    ("infra".to_string(), vec!["kubernetes".to_string(), "aws".to_string()]),
+                ]),
+            }
+        );
+    }
+
+    /// Keeps the written artifacts in memory, by name.
+    struct MemorySink(std::sync::Mutex<BTreeMap<String, Vec<u8>>>);
+    impl ArtifactSink for MemorySink {
+        fn write(&self, name: &str, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+            self.0.lock().unwrap().insert(name.to_string(), bytes.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_plots_to_sink() {
+        let sink = MemorySink(std::sync::Mutex::new(BTreeMap::new()));
+        let task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Daily,
+            "in-memory/counts.png",
+        );
+        let message_counts = vec![("2025-01-01".to_string(), 1), ("2025-01-02".to_string(), 3)];
+        counter_plot_to(&sink, &task, "test messages", &message_counts).expect("Plotting failed");
+
+        let mut ratio_task = task.clone();
+        ratio_task.output_file_name = "in-memory/ratio.svg".to_string();
+        ratio_plot_to(&sink, &ratio_task, "a", &message_counts, "b", &message_counts).expect("Plotting failed");
+
+        let artifacts = sink.0.lock().unwrap();
+        assert_eq!(
+            artifacts.keys().collect::<Vec<_>>(),
+            vec![
+                "in-memory/counts.png",
+                "in-memory/counts.png.csv",
+                "in-memory/ratio.svg",
+                "in-memory/ratio.svg-counts-pattern1.csv",
+                "in-memory/ratio.svg-counts-pattern2.csv",
+            ]
+        );
+        assert!(artifacts["in-memory/counts.png"].starts_with(b"\x89PNG"));
+        assert!(String::from_utf8_lossy(&artifacts["in-memory/ratio.svg"]).contains("<svg"));
+        assert_eq!(artifacts["in-memory/counts.png.csv"], b"2025-01-01,1\n2025-01-02,3\n");
+        assert!(!Path::new("in-memory").exists());
+    }
+
+    #[test]
+    fn test_align_labels() {
+        let named_counts = vec![
+            ("rust".to_string(), vec![("2025-01-01".to_string(), 3), ("2025-01-03".to_string(), 2)]),
+            ("go".to_string(), vec![("2025-01-02".to_string(), 4)]),
+        ];
+        assert_eq!(
+            align_labels(&named_counts),
+            vec![
+                (
+                    "rust".to_string(),
+                    vec![("2025-01-01".to_string(), 3), ("2025-01-02".to_string(), 0), ("2025-01-03".to_string(), 2)]
+                ),
+                (
+                    "go".to_string(),
+                    vec![("2025-01-01".to_string(), 0), ("2025-01-02".to_string(), 4), ("2025-01-03".to_string(), 0)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ratio_plot_smoothing() {
+        let sink = MemorySink(std::sync::Mutex::new(BTreeMap::new()));
+        let mut task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "".to_string(),
+            },
+            TimeResolution::Daily,
+            "in-memory/smoothed.png",
+        );
+        let counts1 = vec![("2025-01-01".to_string(), 0), ("2025-01-02".to_string(), 1), ("2025-01-03".to_string(), 1)];
+        let counts2 = vec![("2025-01-01".to_string(), 1), ("2025-01-02".to_string(), 0), ("2025-01-03".to_string(), 1)];
+        ratio_plot_to(&sink, &task, "a", &counts1, "b", &counts2).expect("Plotting failed");
+        assert!(!sink.0.lock().unwrap().contains_key("in-memory/smoothed.png.csv"));
+
+        task.smoothing_window = Some(3);
+        let ratios = ratio_plot_to(&sink, &task, "a", &counts1, "b", &counts2).expect("Plotting failed");
+        assert_eq!(ratios[1], ("2025-01-02".to_string(), 1.0));
+        let artifacts = sink.0.lock().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&artifacts["in-memory/smoothed.png.csv"]),
+            "label,ratio,smoothed\n2025-01-01,0,0.5\n2025-01-02,1,0.5\n2025-01-03,0.5,0.75\n"
+        );
+    }
+
+    #[test]
+    fn test_html_output() {
+        let sink = MemorySink(std::sync::Mutex::new(BTreeMap::new()));
+        let task = PlotTask::new(
+            Metric::MentionCount {
+                channel_pattern: "".to_string(),
+                message_pattern: "</script>".to_string(),
+            },
+            TimeResolution::Daily,
+            "in-memory/counts.html",
+        );
+        assert_eq!(task.output_format(), OutputFormat::Html);
+        let message_counts = vec![("2025-01-01".to_string(), 1), ("2025-01-02".to_string(), 3)];
+        counter_plot_to(&sink, &task, "'</script>' mentions", &message_counts).expect("Plotting failed");
+        let html = String::from_utf8(sink.0.lock().unwrap()["in-memory/counts.html"].clone()).unwrap();
+        assert!(html.contains("vegaEmbed('#chart'"));
+        assert!(html.contains(r#"{"label":"2025-01-02","series":"'<\/script>' mentions","value":3.0}"#));
+        assert_eq!(html.matches("</script>").count(), 4);
+        assert!(html.contains("<title>Slack '&lt;/script&gt;' mentions over time</title>"));
+
+        let mut grouped_task = task.clone();
+        let output_file = std::env::temp_dir().join("slackrs-test-grouped.html");
+        grouped_task.output_file_name = output_file.to_str().unwrap().to_string();
+        let error = multi_counter_plot(&grouped_task, "mentions", &[("a".to_string(), message_counts)])
+            .expect_err("Grouped histograms cannot be rendered as HTML");
+        assert!(error.to_string().contains("cannot be rendered as HTML"));
+        fs::remove_file(grouped_task.output_file_name + ".csv").unwrap();
+    }
 }
@@ -0,0 +1,18 @@
+use std::{error::Error, fs};
+
+/// A destination for the files written when plotting, i.e. images and CSV files.
+///
+/// The default `FileSink` writes them to the local file system, other implementations could e.g. upload them.
+pub trait ArtifactSink: Sync {
+    /// Writes the artifact with the given name, which is the path it has in the local file system.
+    fn write(&self, name: &str, bytes: &[u8]) -> Result<(), Box<dyn Error>>;
+}
+
+/// Writes artifacts to the local file system, using their names as paths.
+pub struct FileSink;
+impl ArtifactSink for FileSink {
+    fn write(&self, name: &str, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+        fs::write(name, bytes)?;
+        Ok(())
+    }
+}
@@ -1,88 +1,263 @@
 use chrono::prelude::*;
 use lazy_static::lazy_static;
 use regex::Regex;
-use serde::Deserialize;
-use std::fs::File;
+use serde::{Deserialize, de::IgnoredAny};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use zip::ZipArchive;
 
+/// The text Slack exports in place of a deleted message.
+const DELETED_MESSAGE_TEXT: &str = "This message was deleted.";
+
+/// The subtypes of messages that are not written by humans (system events, bots) or that duplicate other
+/// messages (edits, deletions), and hence are not counted by default.
+pub const DEFAULT_EXCLUDED_SUBTYPES: &[&str] = &[
+    "bot_message",
+    "channel_join",
+    "channel_leave",
+    "channel_topic",
+    "channel_purpose",
+    "channel_name",
+    "channel_archive",
+    "channel_unarchive",
+    "group_join",
+    "group_leave",
+    "pinned_item",
+    "unpinned_item",
+    "message_changed",
+    "message_deleted",
+    "tombstone",
+];
+
+/// The minimal number of letters for a message to count as written in all caps (so that e.g. "OK" does not).
+const MIN_ALL_CAPS_LETTERS: usize = 5;
+
 lazy_static! {
     /// The file pattern of the JSON files with the slack messages (there are other JSON files in the export ZIP).
     static ref JSON_FILE_NAME: Regex = Regex::new(r".*\/\d{4}-\d{2}-\d{2}.json$").unwrap();
-}
-
-/// Represents a user profile, part of a Slack `Message`.
-#[derive(Deserialize, Debug)]
-#[allow(dead_code)]
-pub struct UserProfile {
-    avatar_hash: String,
-    /** URL to avatar image. */
-    image_72: String,
-    first_name: String,
-    real_name: String,
-    display_name: String,
-    team: String,
-    name: String,
-    is_restricted: bool,
-    is_ultra_restricted: bool,
+    /// The file with the channel metadata (including members) in the export ZIP.
+    static ref CHANNELS_FILE_NAME: Regex = Regex::new(r"(^|\/)channels.json$").unwrap();
+    /// The file with the user metadata (including names) in the export ZIP.
+    static ref USERS_FILE_NAME: Regex = Regex::new(r"(^|\/)users.json$").unwrap();
+    /// The pattern of emoji shortcodes like `:tada:` or `:+1::skin-tone-2:`.
+    static ref EMOJI_SHORTCODE: Regex = Regex::new(r":[a-z0-9_+'-]+:").unwrap();
+    /// The pattern of URLs in message texts (Slack wraps them as `<url>` or `<url|label>`).
+    static ref URL: Regex = Regex::new(r"https?://[^\s<>|]+").unwrap();
+    /// The pattern of Slack markup in message texts, e.g. mentions like `<@U0123ABC>` or links like `<url|label>`.
+    static ref SLACK_MARKUP: Regex = Regex::new(r"<[^<>]*>").unwrap();
+    /// The pattern of user mentions in message texts, e.g. `<@U0123ABC>` or `<@U0123ABC|alice>`.
+    static ref USER_MENTION: Regex = Regex::new(r"<@([UW][A-Z0-9]+)(\|[^<>]*)?>").unwrap();
 }
 
 /// Represents a Slack message.
+///
+/// Only the fields used by the metrics are kept; all others (e.g. the user profile that Slack repeats in
+/// every message) are skipped while parsing, to keep the memory footprint of large exports small.
 #[derive(Deserialize, Debug)]
-#[allow(dead_code)]
 pub struct Message {
     user: Option<String>,
-    #[serde(rename = "type")]
-    json_type: String,
+    subtype: Option<String>,
     ts: String,
-    client_msg_id: Option<String>,
     pub text: String,
-    team: Option<String>,
-    user_team: Option<String>,
-    source_team: Option<String>,
-    user_profile: Option<UserProfile>,
     thread_ts: Option<String>,
-    parent_user_id: Option<String>,
     attachments: Option<Vec<MessageAttachment>>,
     blocks: Option<Vec<MessageBlock>>,
+    edited: Option<IgnoredAny>,
+    reactions: Option<Vec<MessageReaction>>,
+    bot_id: Option<String>,
+    files: Option<Vec<IgnoredAny>>,
 }
 impl Message {
     #[cfg(test)]
     fn new(user: &str, timestamp: &str, text: &str) -> Message {
         Message {
             user: Option::Some(user.into()),
-            json_type: "message".into(),
+            subtype: Option::None,
             ts: timestamp.into(),
-            client_msg_id: Option::None,
             text: text.into(),
-            team: Option::None,
-            user_team: Option::None,
-            source_team: Option::None,
-            user_profile: Option::None,
             thread_ts: Option::None,
-            parent_user_id: Option::None,
             attachments: Option::None,
             blocks: Option::None,
+            edited: Option::None,
+            reactions: Option::None,
+            bot_id: Option::None,
+            files: Option::None,
         }
     }
 
-    /// Returns the timestamp of the message as a `chrono::DateTime<Utc>`.
+    /// Returns the timestamp of the message as a `chrono::DateTime<Utc>`, or `None` if it is invalid.
     /// We ignore the partial seconds of the timestamp, as we are interested in longer time scales.
-    pub fn time(&self) -> chrono::DateTime<chrono::Utc> {
-        let seconds: i64 = self
+    pub fn time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        parse_timestamp(&self.ts)
+    }
+
+    /// Returns the timestamp of the message including its partial seconds (with microsecond precision).
+    pub fn time_precise(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let time = parse_timestamp(&self.ts)?;
+        let micros: u32 = self
             .ts
-            .split(".")
-            .next()
-            .expect("Invalid timestamp format.")
-            .parse::<i64>()
-            .expect("First part of timestamp is not an integer.");
-        DateTime::from_timestamp(seconds, 0).unwrap()
+            .split('.')
+            .nth(1)
+            .map(|fraction| format!("{:0<6.6}", fraction))
+            .and_then(|fraction| fraction.parse().ok())
+            .unwrap_or(0);
+        Some(time + chrono::TimeDelta::microseconds(micros as i64))
+    }
+
+    /// Returns true if the text consists only of emoji (shortcodes or Unicode) and whitespace.
+    pub fn is_emoji_only(&self) -> bool {
+        let without_shortcodes = EMOJI_SHORTCODE.replace_all(&self.text, "");
+        let has_emoji = without_shortcodes.len() < self.text.len()
+            || without_shortcodes.chars().any(is_emoji_char);
+        has_emoji
+            && without_shortcodes
+                .chars()
+                .all(|c| c.is_whitespace() || is_emoji_char(c))
+    }
+
+    /// Returns true if more than half of the letters of the text are uppercase, ignoring Slack markup and emoji
+    /// shortcodes. Texts with fewer than `MIN_ALL_CAPS_LETTERS` letters do not count as all caps.
+    pub fn is_all_caps(&self) -> bool {
+        let letters: Vec<char> = self.plain_text().chars().filter(|c| c.is_alphabetic()).collect();
+        let uppercase_letters = letters.iter().filter(|c| c.is_uppercase()).count();
+        letters.len() >= MIN_ALL_CAPS_LETTERS && 2 * uppercase_letters > letters.len()
     }
 
-    /// Checks if the message contains a given pattern in its text or in any of its `MessageAttachment`s.
-    pub fn contains(&self, pattern: &str) -> bool {
-        if self.text.contains(pattern) {
+    /// Returns the text without Slack markup (e.g. mentions or links) and emoji shortcodes.
+    pub fn plain_text(&self) -> String {
+        let without_markup = SLACK_MARKUP.replace_all(&self.text, "");
+        EMOJI_SHORTCODE.replace_all(&without_markup, "").into_owned()
+    }
+
+    /// Returns true if the text contains a question mark, ignoring Slack markup (e.g. in links).
+    pub fn is_question(&self) -> bool {
+        SLACK_MARKUP.replace_all(&self.text, "").contains('?')
+    }
+
+    /// Returns the raw Slack timestamp of the message, which also serves as its ID within a channel.
+    pub fn ts(&self) -> &str {
+        &self.ts
+    }
+
+    /// Returns the ID of the user who posted the message, if any.
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    /// Returns the timestamp of the thread the message belongs to.
+    /// Messages outside of threads are treated as the root of their own thread.
+    pub fn thread_ts(&self) -> &str {
+        self.thread_ts.as_deref().unwrap_or(&self.ts)
+    }
+
+    /// Returns true if the message is a reply in a thread (rather than a top-level message or a thread's root).
+    pub fn is_thread_reply(&self) -> bool {
+        self.thread_ts() != self.ts
+    }
+
+    /// Returns the time of the root message of the thread the message belongs to, or `None` if it is invalid.
+    pub fn thread_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        parse_timestamp(self.thread_ts())
+    }
+
+    /// Returns true if the message is a placeholder ('tombstone') for a deleted message.
+    pub fn is_deleted(&self) -> bool {
+        self.subtype.as_deref() == Some("tombstone") || self.text == DELETED_MESSAGE_TEXT
+    }
+
+    /// Returns the subtype of the message (e.g. `channel_join` or `bot_message`); regular messages have none.
+    pub fn subtype(&self) -> Option<&str> {
+        self.subtype.as_deref()
+    }
+
+    /// Returns true if the message has no subtype or one that is not among the excluded subtypes.
+    pub fn is_countable(&self, excluded_subtypes: &[&str]) -> bool {
+        self.subtype().is_none_or(|subtype| !excluded_subtypes.contains(&subtype))
+    }
+
+    /// Returns true if the message was posted by a bot or an integration.
+    pub fn is_bot(&self) -> bool {
+        self.bot_id.is_some() || self.subtype() == Some("bot_message")
+    }
+
+    /// Returns true if the message shares files.
+    pub fn has_files(&self) -> bool {
+        self.subtype() == Some("file_share") || self.files.iter().flatten().next().is_some()
+    }
+
+    /// Returns true if the message has been edited after it was posted.
+    pub fn is_edited(&self) -> bool {
+        self.edited.is_some()
+    }
+
+    /// Returns true if anyone reacted to the message.
+    pub fn has_reactions(&self) -> bool {
+        self.reactions.iter().flatten().next().is_some()
+    }
+
+    /// Returns the total number of reactions to the message (over all emoji).
+    pub fn reaction_count(&self) -> u64 {
+        self.reactions.iter().flatten().map(MessageReaction::count).sum()
+    }
+
+    /// Returns the number of reactions with the given emoji (e.g. `tada` or `:tada:`), including all skin tones.
+    pub fn reaction_count_of(&self, emoji: &str) -> u64 {
+        let emoji = emoji.trim_matches(':');
+        self.reactions
+            .iter()
+            .flatten()
+            .filter(|reaction| reaction.is_emoji(emoji))
+            .map(MessageReaction::count)
+            .sum()
+    }
+
+    /// Returns the users who reacted to the message (with any emoji), as far as the export lists them.
+    pub fn reactors(&self) -> Vec<&str> {
+        self.reactions
+            .iter()
+            .flatten()
+            .flat_map(|reaction| reaction.users.iter().flatten())
+            .map(|user| user.as_str())
+            .collect()
+    }
+
+    /// Returns the time of the first reaction to the message, if any reaction carries a timestamp.
+    ///
+    /// Standard Slack exports do not include reaction timestamps, so this is usually `None`.
+    pub fn first_reaction_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.reactions
+            .iter()
+            .flatten()
+            .filter_map(|reaction| reaction.ts.as_deref())
+            .filter_map(parse_timestamp)
+            .min()
+    }
+
+    /// Returns all URLs of the message, from its text, its `MessageAttachment`s, and its link `MessageBlock`s.
+    pub fn urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = URL
+            .find_iter(&self.text)
+            .map(|x| x.as_str().to_string())
+            .collect();
+        for attachment in self.attachments.iter().flatten() {
+            urls.extend(attachment.urls());
+        }
+        for block in self.blocks.iter().flatten() {
+            urls.extend(block.urls());
+        }
+        urls
+    }
+
+    /// Checks if any of the message's URLs matches the given pattern.
+    pub fn links_to(&self, pattern: &Matcher) -> bool {
+        self.urls().iter().any(|url| pattern.is_match(url))
+    }
+
+    /// Checks if the message matches a given pattern in its text or in any of its `MessageAttachment`s.
+    pub fn contains(&self, pattern: &Matcher) -> bool {
+        if pattern.is_match(&self.text) {
             return true;
         }
         for attachment in self.attachments.iter().flatten() {
@@ -95,22 +270,103 @@ impl Message {
                 return true;
             }
         }
-        return false;
+        false
+    }
+}
+
+/// A pattern to match texts with, compiled once per task.
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    /// Matches texts containing the pattern.
+    Substring(String),
+    /// Matches texts containing the (lowercase) pattern, ignoring case.
+    CaseInsensitiveSubstring(String),
+    /// Matches texts containing a match of the regular expression.
+    Regex(Regex),
+}
+impl Matcher {
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Substring(pattern) => text.contains(pattern.as_str()),
+            Matcher::CaseInsensitiveSubstring(pattern) => text.to_lowercase().contains(pattern.as_str()),
+            Matcher::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+impl From<&str> for Matcher {
+    fn from(pattern: &str) -> Self {
+        Matcher::Substring(pattern.to_string())
     }
 }
 
-/// Represents a message attachment, part of a Slack `Message`.
+/// Checks whether a character is (part of) a Unicode emoji, including modifiers and joiners.
+fn is_emoji_char(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF // pictographs, emoticons, symbols, flags, skin tones
+        | 0x2300..=0x23FF // technical symbols, e.g. watch
+        | 0x2600..=0x27BF // miscellaneous symbols and dingbats
+        | 0x2B00..=0x2BFF // arrows and stars
+        | 0x200D // zero-width joiner
+        | 0x20E3 // combining keycap
+        | 0xFE0F // variation selector
+    )
+}
+
+/// Parses a Slack timestamp (seconds since epoch, with partial seconds after the dot).
+///
+/// Returns `None` if the seconds are not an integer or out of range.
+pub fn parse_timestamp(ts: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let seconds: i64 = ts.split(".").next()?.parse::<i64>().ok()?;
+    DateTime::from_timestamp(seconds, 0)
+}
+
+/// Represents a reaction (an emoji and the users who added it), part of a Slack `Message`.
 #[derive(Deserialize, Debug)]
 #[allow(dead_code)]
+pub struct MessageReaction {
+    name: String,
+    users: Option<Vec<String>>,
+    count: Option<u64>,
+    /// Not part of standard exports, but set by some export tools.
+    ts: Option<String>,
+}
+impl MessageReaction {
+    /// Returns the number of users who reacted, which older exports only list implicitly.
+    fn count(&self) -> u64 {
+        self.count
+            .unwrap_or_else(|| self.users.as_ref().map_or(0, |users| users.len() as u64))
+    }
+
+    /// Returns true if the reaction is the given emoji, with or without a skin tone (e.g. `+1::skin-tone-2`).
+    fn is_emoji(&self, emoji: &str) -> bool {
+        self.name
+            .strip_prefix(emoji)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    }
+}
+
+/// Represents a message attachment, part of a Slack `Message`.
+#[derive(Deserialize, Debug)]
 pub struct MessageAttachment {
-    id: Option<u64>,
     text: Option<String>,
+    title_link: Option<String>,
+    from_url: Option<String>,
+    original_url: Option<String>,
 }
 impl MessageAttachment {
-    /// Returns true if the attachment text contains the given pattern.
-    pub fn contains(&self, pattern: &str) -> bool {
+    /// Returns the URLs the attachment refers to.
+    pub fn urls(&self) -> Vec<String> {
+        [&self.title_link, &self.from_url, &self.original_url]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns true if the attachment text matches the given pattern.
+    pub fn contains(&self, pattern: &Matcher) -> bool {
         if let Some(text) = &self.text {
-            return text.contains(pattern);
+            return pattern.is_match(text);
         }
         false
     }
@@ -118,19 +374,25 @@ impl MessageAttachment {
 
 /// Represents a message block, part of a Slack `Message`. Blocks can be nested.
 #[derive(Deserialize, Debug)]
-#[allow(dead_code)]
 pub struct MessageBlock {
-    #[serde(rename = "type")]
-    json_type: String,
-    block_id: Option<String>,
     text: Option<String>,
+    url: Option<String>,
     elements: Option<Vec<MessageBlock>>,
 }
 impl MessageBlock {
-    /// Returns true if block (or any sub-block) contains the given pattern in its text.
-    pub fn contains(&self, pattern: &str) -> bool {
+    /// Returns the URLs of this block and all of its sub-blocks.
+    pub fn urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = self.url.iter().cloned().collect();
+        for element in self.elements.iter().flatten() {
+            urls.extend(element.urls());
+        }
+        urls
+    }
+
+    /// Returns true if block (or any sub-block) matches the given pattern in its text.
+    pub fn contains(&self, pattern: &Matcher) -> bool {
         if let Some(text) = &self.text {
-            return text.contains(pattern);
+            return pattern.is_match(text);
         }
         if let Some(elements) = &self.elements {
             for element in elements {
@@ -149,68 +411,389 @@ impl MessageBlock {
 /// so this needs to be added to a message after reading the file.
 #[derive(Debug)]
 pub struct MessageInChannel {
+    /// The name of the channel, e.g. `general`.
     pub channel: String,
+    /// The path of the file in the ZIP the message was read from, e.g. `general/2024-03-01.json`.
+    pub file_path: String,
     pub message: Message,
+    /// The display name of the user who posted the message, if known from the export's `users.json`.
+    pub user_name: Option<String>,
 }
 impl MessageInChannel {
-    pub fn new(channel: &str, message: Message) -> MessageInChannel {
+    pub fn new(file_path: &str, message: Message) -> MessageInChannel {
         MessageInChannel {
-            channel: channel.into(),
+            channel: channel_name(file_path).into(),
+            file_path: file_path.into(),
             message,
+            user_name: None,
         }
     }
+
+    /// Returns the display name of the user who posted the message, or their ID if the name is unknown.
+    pub fn user_display_name(&self) -> Option<&str> {
+        self.user_name.as_deref().or(self.message.user())
+    }
+}
+
+/// Represents a user from the export's `users.json`, as far as needed.
+#[derive(Deserialize, Debug)]
+struct User {
+    id: String,
+    name: Option<String>,
+    real_name: Option<String>,
+    profile: Option<UserProfile>,
+}
+
+/// Represents the profile of a `User`, as far as needed.
+#[derive(Deserialize, Debug)]
+struct UserProfile {
+    display_name: Option<String>,
+    real_name: Option<String>,
+}
+
+impl User {
+    /// Returns the name the user chose to be displayed, falling back to their real name and their user name.
+    fn display_name(self) -> Option<String> {
+        let (display_name, profile_real_name) = self
+            .profile
+            .map_or((None, None), |profile| (profile.display_name, profile.real_name));
+        [display_name, profile_real_name, self.real_name, self.name]
+            .into_iter()
+            .flatten()
+            .find(|name| !name.is_empty())
+    }
+}
+
+/// The display names of the users of an export, by user ID.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct UserDirectory {
+    names: HashMap<String, String>,
+}
+impl UserDirectory {
+    /// Returns the display name of the user with the given ID, if known.
+    pub fn name(&self, user_id: &str) -> Option<&str> {
+        self.names.get(user_id).map(String::as_str)
+    }
+
+    /// Sets the display name of the message's user, and rewrites mentions of users in its text to `@display_name`,
+    /// so that patterns can refer to users by name. Unknown users are left as they are.
+    pub fn resolve_users(&self, message: &mut MessageInChannel) {
+        message.user_name = message.message.user().and_then(|user| self.name(user)).map(String::from);
+        if USER_MENTION.is_match(&message.message.text) {
+            message.message.text = self.resolve_mentions(&message.message.text);
+        }
+    }
+
+    /// Rewrites mentions of known users like `<@U0123ABC>` in the text to `@display_name`.
+    pub fn resolve_mentions(&self, text: &str) -> String {
+        USER_MENTION
+            .replace_all(text, |captures: &regex::Captures| match self.name(&captures[1]) {
+                Some(name) => format!("@{}", name),
+                None => captures[0].to_string(),
+            })
+            .into_owned()
+    }
+}
+
+/// Represents a channel from the export's `channels.json`, as far as needed.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Channel {
+    pub name: String,
+    /// When the channel was created, in seconds since epoch.
+    pub created: Option<i64>,
+    pub is_archived: Option<bool>,
+    pub topic: Option<ChannelTopic>,
+    pub members: Option<Vec<String>>,
+}
+impl Channel {
+    /// Returns the time the channel was created, if known.
+    pub fn created_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        DateTime::from_timestamp(self.created?, 0)
+    }
+
+    /// Returns true if the channel has been archived.
+    pub fn is_archived(&self) -> bool {
+        self.is_archived.unwrap_or(false)
+    }
+}
+
+/// Represents the topic of a `Channel`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ChannelTopic {
+    pub value: String,
+}
+
+/// Returns the name of the channel a message file belongs to.
+///
+/// Exports store the messages of each channel as `<channel-name>/<date>.json`, so this is the name of the file's
+/// directory (which also works for exports nested in further directories). Files at the root of the archive do
+/// not belong to any channel directory, so their path is used as it is.
+pub fn channel_name(file_path: &str) -> &str {
+    Path::new(file_path)
+        .parent()
+        .and_then(|directory| directory.file_name())
+        .and_then(|name| name.to_str())
+        .unwrap_or(file_path)
+}
+
+/// Returns the member counts per channel name. Channels without a list of members are left out.
+pub fn members_per_channel(channels: &[Channel]) -> BTreeMap<String, usize> {
+    channels
+        .iter()
+        .filter_map(|channel| Some((channel.name.clone(), channel.members.as_ref()?.len())))
+        .collect()
+}
+
+/// Parses the display names per user ID from the content of `users.json`.
+/// Users without any name are left out.
+fn parse_users(file_content: &str) -> Result<UserDirectory, serde_json::Error> {
+    let users: Vec<User> = serde_json::from_str(file_content)?;
+    let names = users
+        .into_iter()
+        .filter_map(|user| {
+            let id = user.id.clone();
+            Some((id, user.display_name()?))
+        })
+        .collect();
+    Ok(UserDirectory { names })
+}
+
+/// Reads the first metadata file of the export (a ZIP file or a directory) whose path matches the given pattern,
+/// and parses it. Returns `None` if there is no such file, or if it cannot be read or parsed.
+fn read_metadata_file<T>(
+    export_path: &Path,
+    file_name_pattern: &Regex,
+    parse: impl Fn(&str) -> Result<T, serde_json::Error>,
+) -> Option<T> {
+    let parse_file = |file_name: &str, content: &str| match parse(content) {
+        Ok(parsed) => Some(parsed),
+        Err(x) => {
+            eprint!("Could not deserialize '{}': {}.", file_name, x);
+            None
+        }
+    };
+    if export_path.is_dir() {
+        for path in files_in_dir(export_path) {
+            let file_name = path_in_export(export_path, &path);
+            if file_name_pattern.is_match(&file_name)
+                && let Ok(content) = fs::read_to_string(&path)
+                && let Some(parsed) = parse_file(&file_name, &content)
+            {
+                return Some(parsed);
+            }
+        }
+        return None;
+    }
+    let file = File::open(export_path).expect("Cannot open file");
+    let mut archive: ZipArchive<File> = ZipArchive::new(file).expect("ZIP file invalid.");
+    for i in 0..archive.len() {
+        let mut file: zip::read::ZipFile<'_, File> =
+            archive.by_index(i).expect("ZIP file invalid.");
+        if !file.is_dir() && file_name_pattern.is_match(file.name()) {
+            let mut buffer: String = String::new();
+            if file.read_to_string(&mut buffer).is_ok()
+                && let Some(parsed) = parse_file(file.name(), &buffer)
+            {
+                return Some(parsed);
+            }
+        }
+    }
+    None
+}
+
+/// Read the channels from the `channels.json` of the export (a ZIP file or a directory), if it contains one.
+pub fn read_channels(export_path: &Path) -> Vec<Channel> {
+    read_metadata_file(export_path, &CHANNELS_FILE_NAME, |content| serde_json::from_str(content)).unwrap_or_default()
+}
+
+/// Read the member counts per channel name from the `channels.json` of the export (a ZIP file or a directory),
+/// if it contains one.
+pub fn read_channel_members(export_path: &Path) -> BTreeMap<String, usize> {
+    members_per_channel(&read_channels(export_path))
+}
+
+/// Read the display names of all users from the `users.json` of the export (a ZIP file or a directory),
+/// if it contains one.
+pub fn read_user_directory(export_path: &Path) -> UserDirectory {
+    read_metadata_file(export_path, &USERS_FILE_NAME, parse_users).unwrap_or_default()
 }
 
 fn read_file(file_name: &str, file_content: &str) -> Vec<Message> {
     match serde_json::from_str(file_content) {
         Ok(x) => x,
         Err(x) => {
-            eprint!("Could not deserialize '{}': {}.", file_name, x.to_string());
+            eprint!("Could not deserialize '{}': {}.", file_name, x);
             Vec::new()
         }
     }
 }
 
-/// Read ZIP contents.
+/// Reads the messages of a message file, in the channel inferred from the file's path in the export.
+fn read_messages_in_file(file_name: &str, file_content: &str) -> Vec<MessageInChannel> {
+    let messages: Vec<Message> = read_file(file_name, file_content);
+    println!(
+        "Read {:?} bytes into {} messages.",
+        file_content.len(),
+        messages.len()
+    );
+    messages
+        .into_iter()
+        .filter(|x| {
+            let valid = x.time().is_some();
+            if !valid {
+                eprintln!("Skipping message with invalid timestamp '{}' in '{}'.", x.ts, file_name);
+            }
+            valid
+        })
+        .map(|x| MessageInChannel::new(file_name, x))
+        .collect()
+}
+
+/// Iterates over the messages in a ZIP file, reading one message file at a time instead of the whole archive.
+///
+/// Messages come in the order of the files in the archive, so they are not sorted by time (see `read_zip_contents`).
+pub struct ZipMessages {
+    archive: ZipArchive<File>,
+    next_index: usize,
+    files_read: u32,
+    messages: std::vec::IntoIter<MessageInChannel>,
+}
+impl ZipMessages {
+    pub fn open(zip_path: &PathBuf) -> ZipMessages {
+        let file = File::open(zip_path).expect("Cannot open file");
+        let archive: ZipArchive<File> = ZipArchive::new(file).expect("ZIP file invalid.");
+        println!("Number of files in archive: {}", archive.len());
+        ZipMessages {
+            archive,
+            next_index: 0,
+            files_read: 0,
+            messages: Vec::new().into_iter(),
+        }
+    }
+
+    /// The number of message files read so far.
+    pub fn files_read(&self) -> u32 {
+        self.files_read
+    }
+
+    /// Reads the messages of the next message file in the archive, if there is one left.
+    fn read_next_file(&mut self) -> Option<Vec<MessageInChannel>> {
+        while self.next_index < self.archive.len() {
+            let mut file: zip::read::ZipFile<'_, File> =
+                self.archive.by_index(self.next_index).expect("ZIP file invalid.");
+            self.next_index += 1;
+            if !file.is_dir() && JSON_FILE_NAME.is_match(file.name()) {
+                self.files_read += 1;
+                println!("Analyzing file #{}: {}", self.files_read, file.name());
+                let mut buffer: String = String::new();
+                if file.read_to_string(&mut buffer).is_ok() {
+                    return Some(read_messages_in_file(file.name(), &buffer));
+                }
+            }
+        }
+        None
+    }
+}
+impl Iterator for ZipMessages {
+    type Item = MessageInChannel;
+
+    fn next(&mut self) -> Option<MessageInChannel> {
+        loop {
+            if let Some(message) = self.messages.next() {
+                return Some(message);
+            }
+            self.messages = self.read_next_file()?.into_iter();
+        }
+    }
+}
+
+/// Streams the messages of a ZIP file, reading one message file at a time, so that callers can filter them
+/// before collecting them. Messages are not sorted by time (see `read_zip_contents`).
+pub fn stream_messages(zip_path: &PathBuf) -> impl Iterator<Item = MessageInChannel> {
+    let users = read_user_directory(zip_path);
+    ZipMessages::open(zip_path).map(move |mut message| {
+        users.resolve_users(&mut message);
+        message
+    })
+}
+
+/// Read ZIP contents, with the names of the users from the export's `users.json`.
 pub fn read_zip_contents(zip_path: &PathBuf) -> Vec<MessageInChannel> {
-    let file = File::open(zip_path).expect("Cannot open file");
-    let mut archive: ZipArchive<File> = ZipArchive::new(file).expect("ZIP file invalid.");
+    let mut zip_messages = ZipMessages::open(zip_path);
+    let mut result: Vec<MessageInChannel> = zip_messages.by_ref().collect();
+    let users = read_user_directory(zip_path);
+    result.iter_mut().for_each(|message| users.resolve_users(message));
+    sorted_by_time(result, zip_messages.files_read(), zip_path)
+}
+
+/// Read the contents of an export that was already extracted into a directory, like `read_zip_contents`.
+pub fn read_dir_contents(dir: &Path) -> Vec<MessageInChannel> {
     let mut result: Vec<MessageInChannel> = Vec::new();
-    println!("Number of files in archive: {}", archive.len());
     let mut counter: u32 = 0;
-
-    for i in 0..archive.len() {
-        let mut file: zip::read::ZipFile<'_, File> =
-            archive.by_index(i).expect("ZIP file invalid.");
-        if !file.is_dir() && JSON_FILE_NAME.is_match(file.name()) {
+    for path in files_in_dir(dir) {
+        let file_name = path_in_export(dir, &path);
+        if JSON_FILE_NAME.is_match(&file_name) {
             counter += 1;
-            println!("Analyzing file #{}: {}", counter, file.name());
-            let mut buffer: String = String::new();
-            let read_result = file.read_to_string(&mut buffer);
-            if read_result.is_ok() {
-                let messages: Vec<Message> = read_file(file.name(), buffer.as_str());
-                println!(
-                    "Read {:?} bytes into {} messages.",
-                    read_result.unwrap_or(0),
-                    messages.len()
-                );
-                let messages_in_channel: Vec<MessageInChannel> = messages
-                    .into_iter()
-                    .map(|x| MessageInChannel::new(file.name(), x))
-                    .collect();
-                result.extend(messages_in_channel);
+            println!("Analyzing file #{}: {}", counter, file_name);
+            match fs::read_to_string(&path) {
+                Ok(content) => result.extend(read_messages_in_file(&file_name, &content)),
+                Err(x) => eprint!("Could not read '{}': {}.", file_name, x),
             }
         }
     }
+    let users = read_user_directory(dir);
+    result.iter_mut().for_each(|message| users.resolve_users(message));
+    sorted_by_time(result, counter, dir)
+}
+
+/// Read the contents of an export, which is either a ZIP file or a directory it was extracted into.
+pub fn read_export_contents(export_path: &PathBuf) -> Vec<MessageInChannel> {
+    if export_path.is_dir() {
+        read_dir_contents(export_path)
+    } else {
+        read_zip_contents(export_path)
+    }
+}
+
+/// Sorts the messages read from the given number of files of an export by time.
+fn sorted_by_time(mut messages: Vec<MessageInChannel>, file_count: u32, export_path: &Path) -> Vec<MessageInChannel> {
     println!(
-        "Read {} messages from {} files in archive at '{}', sorting by time.",
-        result.len(),
-        counter,
-        zip_path.to_str().unwrap()
+        "Read {} messages from {} files in export at '{}', sorting by time.",
+        messages.len(),
+        file_count,
+        export_path.to_str().unwrap()
     );
-    let mut sorted_results: Vec<MessageInChannel> = result.into_iter().collect();
-    sorted_results.sort_by_key(|x| x.message.time().timestamp_micros());
-    return sorted_results;
+    // Messages with invalid timestamps are skipped while reading, but would be sorted first otherwise
+    messages.sort_by_key(|x| x.message.time());
+    messages
+}
+
+/// Lists all files in the directory and its subdirectories, in a stable order.
+fn files_in_dir(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .expect("Cannot read directory")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    paths.sort();
+    let mut files: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            files.extend(files_in_dir(&path));
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Returns the path of a file relative to the export directory, separated by `/` like the paths in a ZIP file.
+fn path_in_export(dir: &Path, file: &Path) -> String {
+    file.strip_prefix(dir)
+        .unwrap_or(file)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
 #[cfg(test)]
@@ -222,19 +805,271 @@ mod tests {
         let msg1 = Message::new("tester", "123.456", "");
         assert_eq!(
             msg1.time(),
-            Utc.with_ymd_and_hms(1970, 1, 1, 0, 2, 3).unwrap()
+            Some(Utc.with_ymd_and_hms(1970, 1, 1, 0, 2, 3).unwrap())
         );
         let msg2 = Message::new("tester", "1234567", "");
         assert_eq!(
             msg2.time(),
-            Utc.with_ymd_and_hms(1970, 1, 15, 6, 56, 7).unwrap()
+            Some(Utc.with_ymd_and_hms(1970, 1, 15, 6, 56, 7).unwrap())
+        );
+    }
+
+    #[test]
+    fn first_reaction_time() {
+        let message: Message = serde_json::from_str(
+            r#"{
+                "type": "message",
+                "ts": "100.000",
+                "text": "ship it",
+                "reactions": [
+                    {"name": "tada", "users": ["U01"], "count": 1, "ts": "190.000"},
+                    {"name": "+1", "users": ["U02"], "count": 1, "ts": "160.000"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(message.has_reactions());
+        assert_eq!(message.reaction_count(), 2);
+        assert_eq!(
+            message.first_reaction_time(),
+            Some(Utc.with_ymd_and_hms(1970, 1, 1, 0, 2, 40).unwrap())
+        );
+        assert_eq!(Message::new("tester", "100.000", "").first_reaction_time(), None);
+    }
+
+    #[test]
+    fn reaction_count_of_emoji() {
+        let message: Message = serde_json::from_str(
+            r#"{
+                "type": "message",
+                "ts": "100.000",
+                "text": "ship it",
+                "reactions": [
+                    {"name": "+1", "users": ["U01", "U02"], "count": 2},
+                    {"name": "+1::skin-tone-2", "users": ["U03"]},
+                    {"name": "+100", "users": ["U04"], "count": 1},
+                    {"name": "tada", "users": ["U01"], "count": 1}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(message.reaction_count_of("+1"), 3);
+        assert_eq!(message.reaction_count_of(":tada:"), 1);
+        assert_eq!(message.reaction_count_of("rocket"), 0);
+        assert_eq!(message.reaction_count(), 5);
+    }
+
+    #[test]
+    fn ts_to_precise_datetime() {
+        let msg = Message::new("tester", "123.456", "");
+        assert_eq!(
+            msg.time_precise(),
+            Some(Utc.with_ymd_and_hms(1970, 1, 1, 0, 2, 3).unwrap() + chrono::TimeDelta::microseconds(456000))
         );
+        assert_eq!(Message::new("tester", "123", "").time_precise(), msg.time());
+    }
+
+    #[test]
+    fn message_urls() {
+        let message: Message = serde_json::from_str(
+            r#"{
+                "type": "message",
+                "ts": "123.456",
+                "text": "See <https://github.com/roland-ewald/slackrs|the repo> and github",
+                "attachments": [{"id": 1, "from_url": "https://jira.example.com/browse/X-1"}],
+                "blocks": [{"type": "rich_text", "elements": [
+                    {"type": "link", "url": "https://docs.rs/slackrs", "text": "docs"}
+                ]}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            message.urls(),
+            vec![
+                "https://github.com/roland-ewald/slackrs",
+                "https://jira.example.com/browse/X-1",
+                "https://docs.rs/slackrs"
+            ]
+        );
+        assert!(message.links_to(&"jira.example.com".into()));
+        assert!(!message.links_to(&"gitlab".into()));
+    }
+
+    #[test]
+    fn emoji_only() {
+        assert!(Message::new("tester", "123.456", ":tada: 🎉").is_emoji_only());
+        assert!(Message::new("tester", "123.456", " :+1::skin-tone-2: ").is_emoji_only());
+        assert!(Message::new("tester", "123.456", "👍🏽").is_emoji_only());
+        assert!(!Message::new("tester", "123.456", "great :tada:").is_emoji_only());
+        assert!(!Message::new("tester", "123.456", "  ").is_emoji_only());
+    }
+
+    #[test]
+    fn all_caps() {
+        assert!(Message::new("tester", "123.456", "WHO BROKE THE BUILD?! :rage:").is_all_caps());
+        assert!(Message::new("tester", "123.456", "Ship IT NOW").is_all_caps());
+        assert!(!Message::new("tester", "123.456", "Who broke the build?").is_all_caps());
+        assert!(!Message::new("tester", "123.456", "OK 👍").is_all_caps());
+        assert!(!Message::new("tester", "123.456", "<@U0123ABC> thanks :tada:").is_all_caps());
     }
 
     #[test]
-    #[should_panic(expected = "First part of timestamp is not an integer")]
     fn ts_to_datetime_err() {
-        let invalid_time = Message::new("tester", "", "");
-        invalid_time.time();
+        assert_eq!(Message::new("tester", "", "").time(), None);
+        assert_eq!(Message::new("tester", "abc.123", "").time(), None);
+        assert_eq!(Message::new("tester", "99999999999999999.000", "").time_precise(), None);
+    }
+
+    #[test]
+    fn skip_invalid_timestamps() {
+        let messages = read_messages_in_file(
+            "general/2025-01-01.json",
+            r#"[
+                {"type": "message", "ts": "1735725600.000100", "text": "valid"},
+                {"type": "message", "ts": "", "text": "empty"},
+                {"type": "message", "ts": "yesterday", "text": "not numeric"}
+            ]"#,
+        );
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message.text, "valid");
+    }
+
+    #[test]
+    fn channel_members() {
+        let channels: Vec<Channel> = serde_json::from_str(
+            r#"[
+                {"id": "C01", "name": "general", "created": 1735725600, "members": ["U01", "U02", "U03"],
+                 "topic": {"value": "Anything goes", "creator": "U01", "last_set": 1735725600}},
+                {"id": "C02", "name": "archived", "is_archived": true}
+            ]"#,
+        )
+        .unwrap();
+        let members = members_per_channel(&channels);
+        assert_eq!(members.get("general"), Some(&3));
+        assert_eq!(members.get("archived"), None);
+        assert!(!channels[0].is_archived() && channels[1].is_archived());
+        assert_eq!(channels[0].topic.as_ref().map(|topic| topic.value.as_str()), Some("Anything goes"));
+        assert_eq!(channels[0].created_time(), Some(Utc.with_ymd_and_hms(2025, 1, 1, 10, 0, 0).unwrap()));
+        assert_eq!(channels[1].created_time(), None);
+    }
+
+    #[test]
+    fn channel_names() {
+        assert_eq!(channel_name("general/2024-03-01.json"), "general");
+        assert_eq!(channel_name("export/general/2024-03-01.json"), "general");
+        assert_eq!(channel_name("2024-03-01.json"), "2024-03-01.json");
+        assert_eq!(channel_name(""), "");
+
+        let message = MessageInChannel::new("general/2024-03-01.json", Message::new("tester", "123.456", ""));
+        assert_eq!(message.channel, "general");
+        assert_eq!(message.file_path, "general/2024-03-01.json");
+    }
+
+    #[test]
+    fn matchers() {
+        let message = Message::new("tester", "123.456", "Filed a Bug for @Group");
+        assert!(message.contains(&"@Group".into()));
+        assert!(!message.contains(&"@group".into()));
+        assert!(message.contains(&Matcher::CaseInsensitiveSubstring("@group".to_string())));
+        assert!(message.contains(&Matcher::Regex(Regex::new("(?i)bug|issue|ticket").unwrap())));
+        assert!(!message.contains(&Matcher::Regex(Regex::new("issue|ticket").unwrap())));
+    }
+
+    #[test]
+    fn user_directory() {
+        let users = parse_users(
+            r#"[
+                {"id": "U01", "name": "alice", "real_name": "Alice Doe", "profile": {"display_name": "Ali", "real_name": "Alice Doe"}},
+                {"id": "U02", "name": "bob", "profile": {"display_name": "", "real_name": "Bob Roe"}},
+                {"id": "U03", "name": "carol"},
+                {"id": "U04"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(users.name("U01"), Some("Ali"));
+        assert_eq!(users.name("U02"), Some("Bob Roe"));
+        assert_eq!(users.name("U03"), Some("carol"));
+        assert_eq!(users.name("U04"), None);
+
+        let mut message = MessageInChannel::new("general/2025-01-01.json", Message::new("U02", "123.456", "hi"));
+        assert_eq!(message.user_display_name(), Some("U02"));
+        users.resolve_users(&mut message);
+        assert_eq!(message.user_display_name(), Some("Bob Roe"));
+
+        assert_eq!(
+            users.resolve_mentions("<@U01> and <@U03|carol>, please ask <@U09>"),
+            "@Ali and @carol, please ask <@U09>"
+        );
+        let mut mention = MessageInChannel::new("general/2025-01-01.json", Message::new("U02", "123.456", "cc <@U01>"));
+        users.resolve_users(&mut mention);
+        assert!(mention.message.contains(&Matcher::Substring("@Ali".to_string())));
+    }
+
+    #[test]
+    fn dir_contents() {
+        let dir = std::env::temp_dir().join("slackrs-test-dir-contents");
+        let files = [
+            ("general/2025-01-02.json", r#"[{"type": "message", "ts": "1735812000.000100", "text": "later"}]"#),
+            (
+                "random/2025-01-01.json",
+                r#"[{"type": "message", "ts": "1735725600.000100", "text": "first"},
+                    {"type": "message", "ts": "1735729200.000100", "text": "second"}]"#,
+            ),
+            ("users.json", r#"[{"id": "U01", "name": "alice", "profile": {"display_name": "Alice"}}]"#),
+            ("channels.json", r#"[{"id": "C01", "name": "general", "members": ["U01", "U02"]}]"#),
+        ];
+        let zip_path = std::env::temp_dir().join("slackrs-test-dir-contents.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        for (file_name, content) in files {
+            let path = dir.join(file_name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+            zip.start_file(file_name, zip::write::SimpleFileOptions::default()).unwrap();
+            std::io::Write::write_all(&mut zip, content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let summary = |messages: Vec<MessageInChannel>| -> Vec<(String, String)> {
+            messages
+                .into_iter()
+                .map(|x| (x.channel, x.message.text))
+                .collect()
+        };
+        let expected: Vec<(String, String)> = [("random", "first"), ("random", "second"), ("general", "later")]
+            .iter()
+            .map(|(channel, text)| (channel.to_string(), text.to_string()))
+            .collect();
+        assert_eq!(summary(read_export_contents(&dir)), expected);
+        assert_eq!(summary(read_export_contents(&zip_path)), expected);
+        assert_eq!(read_channel_members(&dir), BTreeMap::from([("general".to_string(), 2)]));
+        assert_eq!(read_channel_members(&zip_path), read_channel_members(&dir));
+        assert_eq!(read_user_directory(&dir).name("U01"), Some("Alice"));
+        assert_eq!(read_user_directory(&zip_path), read_user_directory(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_file(&zip_path).unwrap();
+    }
+
+    #[test]
+    fn stream_messages_with_unused_fields() {
+        let zip_path = std::env::temp_dir().join("slackrs-test-stream-messages.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        zip.start_file("general/2025-01-01.json", zip::write::SimpleFileOptions::default()).unwrap();
+        let content = r#"[
+            {"type": "message", "ts": "1735729200.000100", "text": "deploy", "client_msg_id": "abc",
+             "user_profile": {"real_name": "Tester", "image_72": "https://example.com/avatar.png"},
+             "edited": {"user": "U01", "ts": "1735729300.000000"}},
+            {"type": "message", "ts": "1735725600.000100", "text": "lunch", "team": "T01"}
+        ]"#;
+        std::io::Write::write_all(&mut zip, content.as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        let deploy_messages: Vec<MessageInChannel> = stream_messages(&zip_path)
+            .filter(|x| x.message.text.contains("deploy"))
+            .collect();
+        assert_eq!(deploy_messages.len(), 1);
+        assert!(deploy_messages[0].message.is_edited());
+        let texts: Vec<String> = read_zip_contents(&zip_path).into_iter().map(|x| x.message.text).collect();
+        assert_eq!(texts, vec!["lunch", "deploy"]);
+        fs::remove_file(&zip_path).unwrap();
     }
 }
@@ -0,0 +1,330 @@
+use std::fmt;
+
+/// Calculates the z-score of each value relative to the mean and standard deviation
+/// of the (up to) `window` values preceding it.
+///
+/// Buckets without any preceding values, or whose preceding values do not vary, get a z-score of zero.
+pub fn calculate_z_scores(message_counts: &[(String, usize)], window: usize) -> Vec<(String, f64)> {
+    message_counts
+        .iter()
+        .enumerate()
+        .map(|(index, (label, count))| {
+            let preceding: Vec<f64> = message_counts[index.saturating_sub(window)..index]
+                .iter()
+                .map(|(_, count)| *count as f64)
+                .collect();
+            (label.clone(), z_score(*count as f64, &preceding))
+        })
+        .collect()
+}
+
+/// Returns the z-score of `value` with respect to the (population) mean and standard deviation of `sample`.
+fn z_score(value: f64, sample: &[f64]) -> f64 {
+    if sample.is_empty() {
+        return 0.0;
+    }
+    let mean = sample.iter().sum::<f64>() / sample.len() as f64;
+    let variance = sample.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / sample.len() as f64;
+    if variance == 0.0 {
+        0.0
+    } else {
+        (value - mean) / variance.sqrt()
+    }
+}
+
+/// Returns the mean and the (population) standard deviation of the given values, or zeros if there are none.
+pub fn mean_and_std_dev(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+/// Returns the centered moving average of the values over `window` positions.
+///
+/// At the edges, where the full window is not available, the window shrinks to the available values.
+pub fn centered_moving_average(values: &[f64], window: usize) -> Vec<f64> {
+    let before = window.saturating_sub(1) / 2;
+    let after = window / 2;
+    (0..values.len())
+        .map(|index| {
+            let bucket = &values[index.saturating_sub(before)..(index + after + 1).min(values.len())];
+            bucket.iter().sum::<f64>() / bucket.len() as f64
+        })
+        .collect()
+}
+
+/// Returns the median of the given values, or zero if there are none.
+pub fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted: Vec<f64> = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+
+/// Returns the given percentile (0-100) of the values, interpolating linearly between the closest ranks,
+/// or zero if there are no values.
+pub fn percentile(values: &[f64], percentile: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted: Vec<f64> = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = percentile.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Flags the buckets whose count exceeds the median count times `burst_factor`.
+pub fn detect_bursts(message_counts: &[(String, usize)], burst_factor: f64) -> Vec<bool> {
+    let counts: Vec<f64> = message_counts.iter().map(|(_, count)| *count as f64).collect();
+    let threshold = median(&counts) * burst_factor;
+    counts.iter().map(|count| *count > threshold).collect()
+}
+
+/// Calculates the Gini coefficient of the given values (0 for perfect equality, approaching 1 for maximal inequality).
+///
+/// Returns zero if there are no values or if they sum up to zero.
+pub fn gini(values: &[f64]) -> f64 {
+    let total: f64 = values.iter().sum();
+    if values.is_empty() || total == 0.0 {
+        return 0.0;
+    }
+    let mut sorted: Vec<f64> = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let n = sorted.len() as f64;
+    let weighted_sum: f64 = sorted
+        .iter()
+        .enumerate()
+        .map(|(index, value)| (index + 1) as f64 * value)
+        .sum();
+    (2.0 * weighted_sum) / (n * total) - (n + 1.0) / n
+}
+
+/// Finds the shortest contiguous window of hours (possibly wrapping around midnight) that contains
+/// at least the given fraction of all counts, preferring the window with the most counts among equally long ones.
+///
+/// Returns the first and the last hour of the window, or `None` if there are no counts at all.
+pub fn active_hours_window(hour_counts: &[usize; 24], coverage: f64) -> Option<(usize, usize)> {
+    let total: usize = hour_counts.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let target = coverage.clamp(0.0, 1.0) * total as f64;
+    for length in 1..=24 {
+        let best_window = (0..24)
+            .map(|start| {
+                let count: usize = (start..start + length).map(|hour| hour_counts[hour % 24]).sum();
+                (start, count)
+            })
+            .filter(|(_, count)| *count as f64 >= target)
+            .max_by_key(|(start, count)| (*count, 24 - start));
+        if let Some((start, _)) = best_window {
+            return Some((start, (start + length - 1) % 24));
+        }
+    }
+    None
+}
+
+/// Checks whether the hour lies within the window from `start` to `end` (inclusive, possibly wrapping around midnight).
+pub fn hour_in_window(hour: usize, start: usize, end: usize) -> bool {
+    if start <= end {
+        start <= hour && hour <= end
+    } else {
+        hour >= start || hour <= end
+    }
+}
+
+/// Finds the (first) peak of the counts and the number of buckets after it until the count falls to half of the
+/// peak or below, as a measure of how quickly a topic fades after a spike.
+///
+/// Returns the index of the peak and the half-life, which is `None` if the counts never fall to half of the peak
+/// afterwards (e.g. as the peak is the last bucket). Returns `None` if there are no counts at all.
+pub fn half_life(counts: &[usize]) -> Option<(usize, Option<usize>)> {
+    let peak_count = *counts.iter().max().filter(|count| **count > 0)?;
+    let peak = counts.iter().position(|count| *count == peak_count)?;
+    let half_life = counts[peak + 1..]
+        .iter()
+        .position(|count| 2 * count <= peak_count)
+        .map(|offset| offset + 1);
+    Some((peak, half_life))
+}
+
+/// The direction of a series over its most recent values.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Trend {
+    Rising,
+    Falling,
+    Flat,
+}
+impl fmt::Display for Trend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Trend::Rising => write!(f, "rising"),
+            Trend::Falling => write!(f, "falling"),
+            Trend::Flat => write!(f, "flat"),
+        }
+    }
+}
+
+/// Returns the slope of the least-squares line through the values (at positions 0, 1, ...), or zero for fewer than two values.
+pub fn linear_slope(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let n = values.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = values.iter().sum::<f64>() / n;
+    let (covariance, variance) = values.iter().enumerate().fold((0.0, 0.0), |(cov, var), (x, y)| {
+        let dx = x as f64 - mean_x;
+        (cov + dx * (y - mean_y), var + dx * dx)
+    });
+    covariance / variance
+}
+
+/// Classifies the trend of the last `window` values by the slope of a linear fit.
+///
+/// The trend is flat if the fitted line changes by at most `tolerance` times the mean absolute value over the window.
+pub fn trend(values: &[f64], window: usize, tolerance: f64) -> Trend {
+    let recent = &values[values.len().saturating_sub(window)..];
+    if recent.len() < 2 {
+        return Trend::Flat;
+    }
+    let change = linear_slope(recent) * (recent.len() - 1) as f64;
+    let scale = recent.iter().map(|x| x.abs()).sum::<f64>() / recent.len() as f64;
+    if change.abs() <= tolerance * scale {
+        Trend::Flat
+    } else if change > 0.0 {
+        Trend::Rising
+    } else {
+        Trend::Falling
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labeled(counts: &[usize]) -> Vec<(String, usize)> {
+        counts
+            .iter()
+            .enumerate()
+            .map(|(index, count)| (format!("2025-01-{:02}", index + 1), *count))
+            .collect()
+    }
+
+    #[test]
+    fn test_z_score() {
+        // Mean of 5.0 and standard deviation of 2.0
+        assert_eq!(z_score(9.0, &[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), 2.0);
+        assert_eq!(z_score(3.0, &[3.0, 3.0]), 0.0);
+        assert_eq!(z_score(3.0, &[]), 0.0);
+    }
+
+    #[test]
+    fn test_calculate_z_scores() {
+        let z_scores = calculate_z_scores(&labeled(&[2, 4, 4, 4, 5, 5, 7, 9, 9]), 8);
+        assert_eq!(z_scores.len(), 9);
+        assert_eq!(z_scores[0], ("2025-01-01".to_string(), 0.0));
+        assert_eq!(z_scores[8], ("2025-01-09".to_string(), 2.0));
+    }
+
+    #[test]
+    fn test_mean_and_std_dev() {
+        assert_eq!(mean_and_std_dev(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), (5.0, 2.0));
+        assert_eq!(mean_and_std_dev(&[3.0]), (3.0, 0.0));
+        assert_eq!(mean_and_std_dev(&[]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 2.0, 3.0]), 2.5);
+        assert_eq!(median(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&[30.0, 10.0, 20.0], 50.0), 20.0);
+        assert!((percentile(&[10.0, 20.0, 30.0], 90.0) - 28.0).abs() < 1e-9);
+        assert_eq!(percentile(&[10.0, 20.0, 30.0], 100.0), 30.0);
+        assert_eq!(percentile(&[10.0, 20.0, 30.0], 0.0), 10.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+    }
+
+    #[test]
+    fn test_detect_bursts() {
+        assert_eq!(
+            detect_bursts(&labeled(&[1, 2, 2, 10, 3, 5]), 1.5),
+            vec![false, false, false, true, false, true]
+        );
+    }
+
+    #[test]
+    fn test_gini() {
+        assert_eq!(gini(&[1.0, 3.0]), 0.25);
+        assert!((gini(&[0.0, 0.0, 0.0, 10.0]) - 0.75).abs() < 1e-9);
+        assert_eq!(gini(&[2.0, 2.0, 2.0]), 0.0);
+        assert_eq!(gini(&[5.0]), 0.0);
+        assert_eq!(gini(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_half_life() {
+        assert_eq!(half_life(&[1, 2, 8, 6, 5, 4, 1]), Some((2, Some(3))));
+        assert_eq!(half_life(&[8, 3]), Some((0, Some(1))));
+        assert_eq!(half_life(&[1, 8, 6, 5]), Some((1, None)));
+        assert_eq!(half_life(&[1, 2, 8]), Some((2, None)));
+        assert_eq!(half_life(&[0, 0]), None);
+        assert_eq!(half_life(&[]), None);
+    }
+
+    #[test]
+    fn test_active_hours_window() {
+        let mut hour_counts = [0; 24];
+        hour_counts[9..=17].fill(10);
+        hour_counts[3] = 1;
+        assert_eq!(active_hours_window(&hour_counts, 0.9), Some((9, 17)));
+        assert_eq!(active_hours_window(&[0; 24], 0.9), None);
+
+        let mut night_counts = [0; 24];
+        night_counts[22] = 5;
+        night_counts[23] = 5;
+        night_counts[0] = 5;
+        night_counts[12] = 1;
+        assert_eq!(active_hours_window(&night_counts, 0.9), Some((22, 0)));
+        assert!(hour_in_window(23, 22, 0));
+        assert!(!hour_in_window(12, 22, 0));
+    }
+
+    #[test]
+    fn test_centered_moving_average() {
+        let values = [0.0, 1.0, 0.0, 1.0, 1.0];
+        assert_eq!(centered_moving_average(&values, 3), vec![0.5, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0, 1.0]);
+        assert_eq!(centered_moving_average(&values, 4), vec![1.0 / 3.0, 0.5, 0.75, 2.0 / 3.0, 1.0]);
+        assert_eq!(centered_moving_average(&values, 1), values.to_vec());
+        assert!(centered_moving_average(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn test_trend() {
+        assert_eq!(linear_slope(&[1.0, 3.0, 5.0]), 2.0);
+        assert_eq!(trend(&[9.0, 1.0, 2.0, 4.0, 5.0, 7.0], 5, 0.05), Trend::Rising);
+        assert_eq!(trend(&[7.0, 5.0, 4.0, 2.0], 4, 0.05), Trend::Falling);
+        assert_eq!(trend(&[5.0, 5.1, 4.9, 5.0], 4, 0.05), Trend::Flat);
+        assert_eq!(trend(&[0.0, 0.0], 4, 0.05), Trend::Flat);
+        assert_eq!(trend(&[3.0], 4, 0.05), Trend::Flat);
+        assert_eq!(Trend::Rising.to_string(), "rising");
+    }
+}
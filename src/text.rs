@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+
+/// Common English words that say little about the topic of a message, left out of word frequencies by default.
+pub const DEFAULT_STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be", "because", "been",
+    "but", "by", "can", "could", "did", "do", "does", "don't", "for", "from", "get", "got", "had", "has", "have",
+    "he", "her", "here", "him", "his", "how", "i", "i'm", "if", "in", "into", "is", "it", "it's", "its", "just",
+    "let", "me", "more", "my", "no", "not", "now", "of", "on", "one", "or", "our", "out", "she", "so", "some",
+    "than", "that", "the", "their", "them", "then", "there", "they", "this", "to", "too", "up", "us", "was", "we",
+    "were", "what", "when", "where", "which", "who", "will", "with", "would", "you", "your",
+];
+
+/// Splits a (plain) text into lowercase words, i.e. runs of letters, digits, and inner apostrophes that
+/// contain at least one letter (so that numbers are no words).
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || is_apostrophe(c)))
+        .map(|token| token.trim_matches(is_apostrophe))
+        .filter(|token| token.chars().any(char::is_alphabetic))
+        .map(|token| token.replace('’', "'").to_lowercase())
+        .collect()
+}
+
+/// Returns the given stopwords in lowercase, or the default ones if there are none.
+pub fn stopwords(custom_stopwords: Option<&[String]>) -> HashSet<String> {
+    match custom_stopwords {
+        Some(words) => words.iter().map(|word| word.to_lowercase()).collect(),
+        None => DEFAULT_STOPWORDS.iter().map(|word| word.to_string()).collect(),
+    }
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("Don't deploy on Fridays, @team! (v2 is out; 42 bugs)"),
+            vec!["don't", "deploy", "on", "fridays", "team", "v2", "is", "out", "bugs"]
+        );
+        assert_eq!(tokenize("It’s 'quoted' -- ok"), vec!["it's", "quoted", "ok"]);
+        assert!(tokenize("123 ... !!!").is_empty());
+    }
+
+    #[test]
+    fn test_stopwords() {
+        assert!(stopwords(None).contains("the"));
+        let custom_stopwords = stopwords(Some(&["Deploy".to_string()]));
+        assert!(custom_stopwords.contains("deploy"));
+        assert!(!custom_stopwords.contains("the"));
+    }
+}